@@ -0,0 +1,37 @@
+use std::io::{self, Write};
+
+/// Number of bases per line of a FASTA record
+pub const LINE_WIDTH: usize = 80;
+
+/// Writes a FASTA record, the sequence is split in lines of [LINE_WIDTH] bases
+pub fn write_record<W: Write>(out: &mut W, name: &str, sequence: &str) -> io::Result<()> {
+    writeln!(out, ">{}", name)?;
+
+    for line in sequence.as_bytes().chunks(LINE_WIDTH) {
+        out.write_all(line)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_record() {
+        let mut out = Vec::new();
+        write_record(&mut out, "sample#1#A", "ACGT").unwrap();
+        write_record(&mut out, "empty", "").unwrap();
+        write_record(&mut out, "long", &"A".repeat(LINE_WIDTH + 1)).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                ">sample#1#A\nACGT\n>empty\n>long\n{}\nA\n",
+                "A".repeat(LINE_WIDTH)
+            )
+        );
+    }
+}
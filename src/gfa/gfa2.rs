@@ -0,0 +1,256 @@
+//! Parsing of GFA2 records, they are mapped to the same [Entry] variants used for GFA1
+//!
+//! - `S` segments become [Entry::Segment], the declared length is kept in the `LN` tag
+//! - `E` dovetail edges become [Entry::Link] and containments [Entry::Containment]
+//! - `G` gaps become [Entry::Jump]
+//! - `O` ordered groups become [Entry::Path] and `U` unordered groups [Entry::Set]
+//!
+//! Fragments (`F`) and edges that are neither dovetails nor containments are skipped.
+//!
+//! See http://gfa-spec.github.io/GFA-spec/GFA2.html
+
+use std::str::FromStr;
+
+use crate::gfa::{
+    parser::{column, parse_header, parse_orientation, parse_tags, parse_usize, ColumnError},
+    tags::{TagValue, Tags},
+    Entry, Orientation,
+};
+
+/// Parses a single non-empty GFA2 line, returns `None` for record types that are not supported
+pub(super) fn parse_line(line: &str) -> Result<Option<Entry>, ColumnError> {
+    let entry = match line.chars().next() {
+        Some('H') => parse_header(line)?,
+        Some('S') => parse_segment(line)?,
+        Some('E') => return parse_edge(line),
+        Some('G') => parse_gap(line)?,
+        Some('O') => parse_ordered_group(line)?,
+        Some('U') => parse_unordered_group(line)?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(entry))
+}
+
+/// Splits a reference like `12+` into its name and orientation
+fn parse_reference(s: &str, index: usize) -> Result<(String, Orientation), ColumnError> {
+    let orient = s
+        .chars()
+        .last()
+        .ok_or_else(|| ColumnError::new(index, "empty reference"))?;
+    let name = &s[..s.len() - orient.len_utf8()];
+
+    if name.is_empty() {
+        return Err(ColumnError::new(
+            index,
+            format!("invalid reference: {:?}", s),
+        ));
+    }
+
+    Ok((
+        name.to_string(),
+        parse_orientation(&orient.to_string(), index)?,
+    ))
+}
+
+/// Parses a position like `42` or `100$`, the flag tells if the position is the end of the
+/// segment
+fn parse_position(columns: &[&str], index: usize) -> Result<(usize, bool), ColumnError> {
+    let value = column(columns, index, "position")?;
+    let (value, is_end) = match value.strip_suffix('$') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+
+    let position = usize::from_str(value)
+        .map_err(|_| ColumnError::new(index, format!("invalid position: {:?}", value)))?;
+
+    Ok((position, is_end))
+}
+
+/// Keeps the optional identifier of a record as an `ID` tag
+fn with_id(mut tags: Tags, id: &str) -> Tags {
+    if id != "*" {
+        tags.insert("ID", TagValue::String(id.to_string()));
+    }
+
+    tags
+}
+
+/// Parse a line of the source file into a Segment struct
+///
+/// ```txt
+/// S  1  4  ACGT
+/// ```
+fn parse_segment(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    let length = parse_usize(&columns, 2, "segment length")?;
+
+    let mut tags = parse_tags(&columns, 4)?;
+    tags.insert("LN", TagValue::Int(length as i64));
+
+    Ok(Entry::Segment {
+        id: column(&columns, 1, "segment name")?.to_string(),
+        sequence: column(&columns, 3, "sequence")?.to_string(),
+        tags,
+    })
+}
+
+/// Parse a line of the source file into a Link or Containment struct, depending on which ends
+/// of the two segments are covered by the alignment
+///
+/// ```txt
+/// E  *  1+  2-  10  20$  0  10  10M
+/// ```
+fn parse_edge(line: &str) -> Result<Option<Entry>, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    let id = column(&columns, 1, "edge id")?;
+    let (from, from_orient) = parse_reference(column(&columns, 2, "first segment")?, 2)?;
+    let (to, to_orient) = parse_reference(column(&columns, 3, "second segment")?, 3)?;
+
+    let (from_begin, _) = parse_position(&columns, 4)?;
+    let (_, from_end_is_last) = parse_position(&columns, 5)?;
+    let (to_begin, _) = parse_position(&columns, 6)?;
+    let (_, to_end_is_last) = parse_position(&columns, 7)?;
+
+    let alignment = column(&columns, 8, "alignment")?.to_string();
+    let tags = with_id(parse_tags(&columns, 9)?, id);
+
+    // whether the alignment covers the start or the end of each segment
+    let from_covers = (from_begin == 0, from_end_is_last);
+    let to_covers = (to_begin == 0, to_end_is_last);
+
+    if to_covers == (true, true) {
+        return Ok(Some(Entry::Containment {
+            container: from,
+            container_orient: from_orient,
+            contained: to,
+            contained_orient: to_orient,
+            pos: from_begin,
+            overlap: alignment,
+            tags,
+        }));
+    }
+
+    if from_covers == (true, true) {
+        return Ok(Some(Entry::Containment {
+            container: to,
+            container_orient: to_orient,
+            contained: from,
+            contained_orient: from_orient,
+            pos: to_begin,
+            overlap: alignment,
+            tags,
+        }));
+    }
+
+    // start and end of each segment once oriented as in the edge
+    let oriented = |(covers_begin, covers_end), orient| match orient {
+        Orientation::Forward => (covers_begin, covers_end),
+        Orientation::Reverse => (covers_end, covers_begin),
+    };
+
+    let (from_start, from_end) = oriented(from_covers, from_orient);
+    let (to_start, to_end) = oriented(to_covers, to_orient);
+
+    let entry = if from_end && to_start {
+        Entry::Link {
+            from,
+            from_orient,
+            to,
+            to_orient,
+            overlap: alignment,
+            tags,
+        }
+    } else if to_end && from_start {
+        Entry::Link {
+            from: to,
+            from_orient: to_orient,
+            to: from,
+            to_orient: from_orient,
+            overlap: alignment,
+            tags,
+        }
+    } else {
+        // internal alignment, these can't be represented as links
+        return Ok(None);
+    };
+
+    Ok(Some(entry))
+}
+
+/// Parse a line of the source file into a Jump struct, the variance is kept in the `VR` tag
+///
+/// ```txt
+/// G  g1  1+  2-  500  50
+/// ```
+fn parse_gap(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    let (from, from_orient) = parse_reference(column(&columns, 2, "first segment")?, 2)?;
+    let (to, to_orient) = parse_reference(column(&columns, 3, "second segment")?, 3)?;
+
+    let distance = column(&columns, 4, "distance")?;
+    let distance = i64::from_str(distance)
+        .map_err(|_| ColumnError::new(4, format!("invalid distance: {:?}", distance)))?;
+
+    let mut tags = with_id(parse_tags(&columns, 6)?, column(&columns, 1, "gap id")?);
+
+    match column(&columns, 5, "variance")? {
+        "*" => {}
+        variance => {
+            let variance = i64::from_str(variance)
+                .map_err(|_| ColumnError::new(5, format!("invalid variance: {:?}", variance)))?;
+
+            tags.insert("VR", TagValue::Int(variance));
+        }
+    }
+
+    Ok(Entry::Jump {
+        from,
+        from_orient,
+        to,
+        to_orient,
+        distance: Some(distance),
+        tags,
+    })
+}
+
+/// Parse a line of the source file into a Path struct
+///
+/// ```txt
+/// O  p1  1+ 2- 3+
+/// ```
+fn parse_ordered_group(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    Ok(Entry::Path {
+        name: column(&columns, 1, "group id")?.to_string(),
+        segments: column(&columns, 2, "references")?
+            .split_whitespace()
+            .map(|reference| parse_reference(reference, 2))
+            .collect::<Result<_, _>>()?,
+        overlaps: Vec::new(),
+        tags: parse_tags(&columns, 3)?,
+    })
+}
+
+/// Parse a line of the source file into a Set struct
+///
+/// ```txt
+/// U  s1  1 2 3
+/// ```
+fn parse_unordered_group(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    Ok(Entry::Set {
+        name: column(&columns, 1, "group id")?.to_string(),
+        members: column(&columns, 2, "members")?
+            .split_whitespace()
+            .map(|member| member.to_string())
+            .collect(),
+        tags: parse_tags(&columns, 3)?,
+    })
+}
@@ -1,4 +1,6 @@
-use std::fmt::Display;
+use std::{fmt::Display, io};
+
+use tags::Tags;
 
 #[derive(Debug, Hash, PartialEq, PartialOrd, Ord, Eq, Copy, Clone)]
 pub enum Orientation {
@@ -6,6 +8,15 @@ pub enum Orientation {
     Reverse,
 }
 
+impl Orientation {
+    pub fn flip(self) -> Self {
+        match self {
+            Orientation::Forward => Orientation::Reverse,
+            Orientation::Reverse => Orientation::Forward,
+        }
+    }
+}
+
 impl Display for Orientation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -15,25 +26,128 @@ impl Display for Orientation {
     }
 }
 
+/// Length of the overlap of a link on its second segment, summing the CIGAR operations that
+/// consume it (`M`, `I`, `=`, `X`). Returns `None` for a missing (`*`) or malformed overlap.
+pub fn overlap_length(cigar: &str) -> Option<usize> {
+    if cigar == "*" || cigar.is_empty() {
+        return None;
+    }
+
+    let mut length = 0;
+    let mut count = String::new();
+
+    for c in cigar.chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+            continue;
+        }
+
+        let n = count.parse::<usize>().ok()?;
+        count.clear();
+
+        match c {
+            'M' | 'I' | '=' | 'X' => length += n,
+            'D' | 'N' | 'S' | 'H' | 'P' => {}
+            _ => return None,
+        }
+    }
+
+    count.is_empty().then_some(length)
+}
+
+/// CIGAR of the overlap of a link walked on the opposite strand, the operations are reversed
+/// and insertions and deletions are swapped as the two segments exchange their roles. A missing
+/// (`*`) or malformed overlap is returned unchanged.
+pub fn reverse_overlap(cigar: &str) -> String {
+    let mut operations = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in cigar.char_indices() {
+        if c.is_ascii_digit() {
+            continue;
+        }
+
+        let operation = match c {
+            'I' => 'D',
+            'D' => 'I',
+            'M' | '=' | 'X' | 'N' | 'S' | 'H' | 'P' => c,
+            _ => return cigar.to_string(),
+        };
+
+        operations.push((&cigar[start..i], operation));
+        start = i + c.len_utf8();
+    }
+
+    if start != cigar.len() {
+        return cigar.to_string();
+    }
+
+    operations
+        .iter()
+        .rev()
+        .map(|(count, operation)| format!("{}{}", count, operation))
+        .collect()
+}
+
+/// Payload of an edge read from a GFA file, a link (`L` line) with the CIGAR of its overlap or
+/// a jump (`J` line) with its distance. The segments of a jump are not adjacent in the sequence,
+/// there is a gap between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Connection {
+    Link(String),
+    Jump(Option<i64>),
+}
+
+impl Connection {
+    /// Length of the overlap of a link, 0 if unknown, `None` for a jump
+    pub fn overlap_length(&self) -> Option<usize> {
+        match self {
+            Connection::Link(cigar) => Some(overlap_length(cigar).unwrap_or(0)),
+            Connection::Jump(_) => None,
+        }
+    }
+
+    /// The connection as walked on the opposite strand, see [reverse_overlap]
+    pub fn reversed(&self) -> Connection {
+        match self {
+            Connection::Link(cigar) => Connection::Link(reverse_overlap(cigar)),
+            Connection::Jump(distance) => Connection::Jump(*distance),
+        }
+    }
+}
+
+/// A link with an unknown overlap
+impl Default for Connection {
+    fn default() -> Self {
+        Connection::Link(String::new())
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum Entry {
     Header {
-        version: String,
+        version: Option<String>,
+        tags: Tags,
     },
     Segment {
         id: String,
         sequence: String,
+        tags: Tags,
     },
     Link {
         from: String,
         from_orient: Orientation,
         to: String,
         to_orient: Orientation,
+        overlap: String,
+        tags: Tags,
     },
     Path {
         name: String,
         segments: Vec<(String, Orientation)>,
+        overlaps: Vec<String>,
+        tags: Tags,
     },
     Walk {
         sample: String,
@@ -44,7 +158,139 @@ pub enum Entry {
         seq_end: usize,
 
         segments: Vec<(String, Orientation)>,
+        tags: Tags,
+    },
+    Containment {
+        container: String,
+        container_orient: Orientation,
+        contained: String,
+        contained_orient: Orientation,
+
+        /// 0-based position of the contained segment in the container
+        pos: usize,
+        overlap: String,
+        tags: Tags,
+    },
+    Jump {
+        from: String,
+        from_orient: Orientation,
+        to: String,
+        to_orient: Orientation,
+
+        /// Estimated gap between the two segments, `None` when unknown ("*")
+        distance: Option<i64>,
+        tags: Tags,
+    },
+    /// Unordered group of GFA2 (U lines), ordered groups are read as paths
+    Set {
+        name: String,
+        members: Vec<String>,
+        tags: Tags,
     },
 }
 
+impl Entry {
+    pub fn tags(&self) -> &Tags {
+        match self {
+            Entry::Header { tags, .. }
+            | Entry::Segment { tags, .. }
+            | Entry::Link { tags, .. }
+            | Entry::Path { tags, .. }
+            | Entry::Walk { tags, .. }
+            | Entry::Containment { tags, .. }
+            | Entry::Jump { tags, .. }
+            | Entry::Set { tags, .. } => tags,
+        }
+    }
+}
+
+/// Error produced while reading a GFA source
+#[derive(Debug)]
+pub enum GfaError {
+    Io(io::Error),
+    Parse {
+        /// 1-based line number in the source
+        line: usize,
+        record_type: char,
+        /// 1-based column index, the record type is column 1
+        column: usize,
+        reason: String,
+    },
+}
+
+impl Display for GfaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GfaError::Io(err) => write!(f, "{}", err),
+            GfaError::Parse {
+                line,
+                record_type,
+                column,
+                reason,
+            } => write!(
+                f,
+                "line {}: invalid {} record at column {}: {}",
+                line, record_type, column, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GfaError {}
+
+impl From<io::Error> for GfaError {
+    fn from(err: io::Error) -> Self {
+        GfaError::Io(err)
+    }
+}
+
+impl From<GfaError> for io::Error {
+    fn from(err: GfaError) -> Self {
+        match err {
+            GfaError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+mod gfa2;
 pub mod parser;
+pub mod tags;
+pub mod writer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlap_length() {
+        assert_eq!(overlap_length("0M"), Some(0));
+        assert_eq!(overlap_length("5M2I3D1M"), Some(8));
+        assert_eq!(overlap_length("*"), None);
+        assert_eq!(overlap_length("5"), None);
+        assert_eq!(overlap_length("M"), None);
+        assert_eq!(overlap_length("3Q"), None);
+    }
+
+    #[test]
+    fn test_reverse_overlap() {
+        assert_eq!(reverse_overlap("3M"), "3M");
+        assert_eq!(reverse_overlap("5M2I3D1M"), "1M3I2D5M");
+        assert_eq!(overlap_length(&reverse_overlap("5M2I3D1M")), Some(9));
+        assert_eq!(reverse_overlap("*"), "*");
+        assert_eq!(reverse_overlap("5"), "5");
+        assert_eq!(reverse_overlap("3Q"), "3Q");
+    }
+
+    #[test]
+    fn test_connection() {
+        let link = Connection::Link("5M2I3D1M".to_string());
+        assert_eq!(link.overlap_length(), Some(8));
+        assert_eq!(link.reversed(), Connection::Link("1M3I2D5M".to_string()));
+        assert_eq!(Connection::default().overlap_length(), Some(0));
+
+        let jump = Connection::Jump(Some(50));
+        assert_eq!(jump.overlap_length(), None);
+        assert_eq!(jump.reversed(), jump);
+    }
+}
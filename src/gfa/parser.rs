@@ -1,36 +1,93 @@
 #![allow(dead_code)]
 
 use std::{
+    fs::File,
     io::{self, BufRead, BufReader, Read},
     str::FromStr,
 };
 
-use indicatif::ProgressIterator;
+use flate2::bufread::MultiGzDecoder;
+use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::gfa::{Entry, Orientation};
+use crate::gfa::{
+    gfa2,
+    tags::{Tag, Tags},
+    Entry, GfaError, Orientation,
+};
+
+/// Error local to a single line, the line number and record type get attached by the caller
+pub(super) struct ColumnError {
+    column: usize,
+    reason: String,
+}
 
-fn parse_orientation(s: &str) -> Orientation {
+impl ColumnError {
+    pub(super) fn new(column: usize, reason: impl Into<String>) -> Self {
+        ColumnError {
+            column,
+            reason: reason.into(),
+        }
+    }
+}
+
+pub(super) fn column<'a>(
+    columns: &[&'a str],
+    index: usize,
+    name: &str,
+) -> Result<&'a str, ColumnError> {
+    match columns.get(index) {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(ColumnError::new(index, format!("missing {}", name))),
+    }
+}
+
+pub(super) fn parse_usize(
+    columns: &[&str],
+    index: usize,
+    name: &str,
+) -> Result<usize, ColumnError> {
+    let value = column(columns, index, name)?;
+
+    usize::from_str(value)
+        .map_err(|_| ColumnError::new(index, format!("invalid {}: {:?}", name, value)))
+}
+
+pub(super) fn parse_orientation(s: &str, index: usize) -> Result<Orientation, ColumnError> {
     match s {
-        "+" => Orientation::Forward,
-        ">" => Orientation::Forward,
-        "-" => Orientation::Reverse,
-        "<" => Orientation::Reverse,
-        _ => panic!("Invalid orientation: {}", s),
+        "+" => Ok(Orientation::Forward),
+        ">" => Ok(Orientation::Forward),
+        "-" => Ok(Orientation::Reverse),
+        "<" => Ok(Orientation::Reverse),
+        _ => Err(ColumnError::new(
+            index,
+            format!("invalid orientation: {:?}", s),
+        )),
     }
 }
 
+/// Parses the optional fields of a record starting from the column at index `from`
+pub(super) fn parse_tags(columns: &[&str], from: usize) -> Result<Tags, ColumnError> {
+    columns
+        .iter()
+        .enumerate()
+        .skip(from)
+        .map(|(index, tag)| Tag::from_str(tag).map_err(|reason| ColumnError::new(index, reason)))
+        .collect()
+}
+
 /// Parse a line of the source file into a Header struct
 ///
 /// ```txt
 /// H  VN:Z:1.0
 /// ```
 ///
-fn parse_header(line: &str) -> Entry {
-    let columns: Vec<&str> = line.split(':').collect();
+pub(super) fn parse_header(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
 
-    Entry::Header {
-        version: columns[2].to_string(),
-    }
+    let tags = parse_tags(&columns, 1)?;
+    let version = tags.get_str("VN").map(|version| version.to_string());
+
+    Ok(Entry::Header { version, tags })
 }
 
 /// Parse a line of the source file into a Segment struct
@@ -38,151 +95,667 @@ fn parse_header(line: &str) -> Entry {
 /// ```txt
 /// S  1  ACGT
 /// ```
-fn parse_segment(line: &str) -> Entry {
+fn parse_segment(line: &str) -> Result<Entry, ColumnError> {
     let columns: Vec<&str> = line.split('\t').collect();
 
-    Entry::Segment {
-        id: columns[1].to_string(),
-        sequence: columns[2].to_string(),
-    }
+    Ok(Entry::Segment {
+        id: column(&columns, 1, "segment name")?.to_string(),
+        sequence: column(&columns, 2, "sequence")?.to_string(),
+        tags: parse_tags(&columns, 3)?,
+    })
 }
 
 /// Parse a line of the source file into a Link struct
 ///
 /// ```txt
-/// L  1  +  2  -  3M
+/// L  1  +  2  -  3M  RC:i:4
 /// ```
-fn parse_link(line: &str) -> Entry {
+fn parse_link(line: &str) -> Result<Entry, ColumnError> {
     let columns: Vec<&str> = line.split('\t').collect();
 
-    Entry::Link {
-        from: columns[1].to_string(),
-        from_orient: parse_orientation(columns[2]),
-        to: columns[3].to_string(),
-        to_orient: parse_orientation(columns[4]),
-    }
+    Ok(Entry::Link {
+        from: column(&columns, 1, "from segment")?.to_string(),
+        from_orient: parse_orientation(column(&columns, 2, "from orientation")?, 2)?,
+        to: column(&columns, 3, "to segment")?.to_string(),
+        to_orient: parse_orientation(column(&columns, 4, "to orientation")?, 4)?,
+        overlap: columns.get(5).copied().unwrap_or("*").to_string(),
+        tags: parse_tags(&columns, 6)?,
+    })
 }
 
 /// Parse a line of the source file into a Path struct
 ///
 /// ```txt
-/// P	A	11+,12+,14+,15-,17+	*,*,*,*
+/// P  A  11+,12+,14+,15-,17+  *,*,*,*
 /// ```
-fn parse_path(line: &str) -> Entry {
+fn parse_path(line: &str) -> Result<Entry, ColumnError> {
     let columns: Vec<&str> = line.split('\t').collect();
 
-    Entry::Path {
-        name: columns[1].to_string(),
-        segments: columns[2]
+    Ok(Entry::Path {
+        name: column(&columns, 1, "path name")?.to_string(),
+        segments: column(&columns, 2, "segment names")?
             .split(',')
             .map(|s| {
-                let (name, orient) = s.split_at(s.len() - 1);
-                (name.to_string(), parse_orientation(orient))
+                let orient = s
+                    .chars()
+                    .last()
+                    .ok_or_else(|| ColumnError::new(2, "empty segment name"))?;
+                let name = &s[..s.len() - orient.len_utf8()];
+
+                if name.is_empty() {
+                    return Err(ColumnError::new(2, format!("invalid path step: {:?}", s)));
+                }
+
+                Ok((name.to_string(), parse_orientation(&orient.to_string(), 2)?))
             })
-            .collect(),
+            .collect::<Result<_, _>>()?,
+        overlaps: parse_path_overlaps(columns.get(3).copied().unwrap_or("*")),
+        tags: parse_tags(&columns, 4)?,
+    })
+}
+
+/// Parses the overlaps column of a path, a single "*" means that overlaps are not specified
+fn parse_path_overlaps(s: &str) -> Vec<String> {
+    if s == "*" {
+        return Vec::new();
     }
+
+    s.split(',').map(|overlap| overlap.to_string()).collect()
 }
 
-fn parse_path_segments(s: &str) -> Vec<(String, Orientation)> {
+fn parse_path_segments(s: &str, index: usize) -> Result<Vec<(String, Orientation)>, ColumnError> {
     let mut result = Vec::new();
     let mut rest = s;
 
     loop {
-        // println!("Rest: {}", rest);
-
         let r = rest;
 
-        let (orient, r) = r.split_at(1);
+        let orient = r.get(..1).unwrap_or("");
+        let r = &r[orient.len()..];
         let (name, r) = r.split_at(r.find(['<', '>']).unwrap_or(r.len()));
 
+        if name.is_empty() {
+            return Err(ColumnError::new(
+                index,
+                format!("invalid walk step in {:?}", s),
+            ));
+        }
+
         rest = r;
-        result.push((name.to_string(), parse_orientation(orient)));
+        result.push((name.to_string(), parse_orientation(orient, index)?));
 
         if rest.is_empty() {
             break;
         }
     }
 
-    result
+    Ok(result)
 }
 
 /// Parse a line of the source file into a Walk struct
 ///
 /// ```txt
-/// W	sample	1	A	0	5	>11>12>14>15>17
+/// W  sample  1  A  0  5  >11>12>14>15>17
 /// ```
-fn parse_walk(line: &str) -> Entry {
+fn parse_walk(line: &str) -> Result<Entry, ColumnError> {
     let columns: Vec<&str> = line.split('\t').collect();
 
-    Entry::Walk {
-        sample: columns[1].to_string(),
+    Ok(Entry::Walk {
+        sample: column(&columns, 1, "sample id")?.to_string(),
+
+        haplotype_index: parse_usize(&columns, 2, "haplotype index")?,
+        seq_id: column(&columns, 3, "sequence id")?.to_string(),
+        seq_start: parse_usize(&columns, 4, "sequence start")?,
+        seq_end: parse_usize(&columns, 5, "sequence end")?,
 
-        haplotype_index: usize::from_str(columns[2]).unwrap(),
-        seq_id: columns[3].to_string(),
-        seq_start: usize::from_str(columns[4]).unwrap(),
-        seq_end: usize::from_str(columns[5]).unwrap(),
+        segments: parse_path_segments(column(&columns, 6, "walk")?, 6)?,
+        tags: parse_tags(&columns, 7)?,
+    })
+}
 
-        segments: parse_path_segments(columns[6]),
+/// Parse a line of the source file into a Containment struct
+///
+/// ```txt
+/// C  1  +  2  -  12  4M
+/// ```
+fn parse_containment(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    Ok(Entry::Containment {
+        container: column(&columns, 1, "container segment")?.to_string(),
+        container_orient: parse_orientation(column(&columns, 2, "container orientation")?, 2)?,
+        contained: column(&columns, 3, "contained segment")?.to_string(),
+        contained_orient: parse_orientation(column(&columns, 4, "contained orientation")?, 4)?,
+        pos: parse_usize(&columns, 5, "position")?,
+        overlap: column(&columns, 6, "overlap")?.to_string(),
+        tags: parse_tags(&columns, 7)?,
+    })
+}
+
+/// Parse a line of the source file into a Jump struct (GFA 1.2)
+///
+/// ```txt
+/// J  1  +  2  -  100  SC:i:1
+/// ```
+fn parse_jump(line: &str) -> Result<Entry, ColumnError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+
+    let distance = match column(&columns, 5, "distance")? {
+        "*" => None,
+        distance => Some(
+            i64::from_str(distance)
+                .map_err(|_| ColumnError::new(5, format!("invalid distance: {:?}", distance)))?,
+        ),
+    };
+
+    Ok(Entry::Jump {
+        from: column(&columns, 1, "from segment")?.to_string(),
+        from_orient: parse_orientation(column(&columns, 2, "from orientation")?, 2)?,
+        to: column(&columns, 3, "to segment")?.to_string(),
+        to_orient: parse_orientation(column(&columns, 4, "to orientation")?, 4)?,
+        distance,
+        tags: parse_tags(&columns, 6)?,
+    })
+}
+
+/// Parses a single non-empty line, returns `None` for record types that are not supported
+fn parse_line(line: &str, gfa2: bool) -> Result<Option<Entry>, ColumnError> {
+    if gfa2 {
+        return gfa2::parse_line(line);
+    }
+
+    let entry = match line.chars().next() {
+        Some('H') => parse_header(line)?,
+        Some('S') => parse_segment(line)?,
+        Some('L') => parse_link(line)?,
+        Some('P') => parse_path(line)?,
+        Some('W') => parse_walk(line)?,
+        Some('C') => parse_containment(line)?,
+        Some('J') => parse_jump(line)?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(entry))
+}
+
+/// Opens a file for reading, the returned reader updates a progress bar with the number of bytes
+/// read so far. The total is taken from the file metadata when available (e.g. not for pipes).
+///
+/// Gzip and bgzip compressed files are detected from their magic bytes and decompressed on the
+/// fly, in this case the progress refers to the compressed bytes.
+pub fn open_file(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    let size = file
+        .metadata()
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len());
+
+    decompressed(BufReader::new(progress_bar(size).wrap_read(file)))
+}
+
+/// Opens the input source, either a file or stdin when the path is "-"
+pub fn open_input(path: &str) -> io::Result<Box<dyn BufRead>> {
+    if path == "-" {
+        return decompressed(BufReader::new(progress_bar(None).wrap_read(io::stdin())));
+    }
+
+    open_file(path)
+}
+
+/// Wraps the reader in a gzip decoder if the source starts with the gzip magic bytes. Bgzip files
+/// are just a sequence of gzip members so they are handled by the same multi-member decoder.
+pub fn decompressed<R: BufRead + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead>> {
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+fn progress_bar(size: Option<u64>) -> ProgressBar {
+    match size {
+        Some(size) => ProgressBar::new(size).with_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+            )
+            .expect("valid template"),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("[{elapsed_precise}] {spinner} {bytes} ({bytes_per_sec})")
+                .expect("valid template"),
+        ),
     }
 }
 
-pub fn parse_file(file: &str) -> io::Result<Vec<Entry>> {
-    let file_lines_count = BufReader::new(std::fs::File::open(file)?)
-        .lines()
-        .progress_count(0)
-        .count() as u64;
+pub fn parse_file(file: &str) -> Result<Vec<Entry>, GfaError> {
+    parse_source(open_file(file)?)
+}
+
+/// Parses the whole source stopping at the first malformed line
+pub fn parse_source<R: Read>(reader: R) -> Result<Vec<Entry>, GfaError> {
+    parse_source_with(reader, Err)
+}
+
+/// Parses the whole source skipping malformed lines, the errors for the skipped lines are
+/// returned along with the parsed entries
+pub fn parse_source_lenient<R: Read>(reader: R) -> io::Result<(Vec<Entry>, Vec<GfaError>)> {
+    let mut errors = Vec::new();
 
-    let file = std::fs::File::open(file)?;
+    let entries = parse_source_with(reader, |err| match err {
+        GfaError::Parse { .. } => {
+            errors.push(err);
+            Ok(())
+        }
+        err => Err(err),
+    })
+    .map_err(io::Error::from)?;
 
-    parse_source(file, file_lines_count)
+    Ok((entries, errors))
 }
 
-pub fn parse_source<R: Read>(reader: R, line_count: u64) -> io::Result<Vec<Entry>> {
+fn parse_source_with<R, F>(reader: R, mut on_error: F) -> Result<Vec<Entry>, GfaError>
+where
+    R: Read,
+    F: FnMut(GfaError) -> Result<(), GfaError>,
+{
     let mut entries = Vec::new();
-    let mut skipped = Vec::new();
 
     println!("Parsing GFA file...");
 
-    for line in BufReader::new(reader).lines().progress_count(line_count) {
-        let line = line?;
-        let line = line.trim();
+    let mut reader = GfaReader::new(BufReader::new(reader));
+    for entry in &mut reader {
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(err) => on_error(err)?,
+        }
+    }
+
+    reader.print_skipped();
+
+    Ok(entries)
+}
+
+/// Streaming reader over the entries of a GFA source, lines are read and parsed one at a
+/// time when the iterator is advanced so memory usage doesn't depend on the size of the file.
+///
+/// Lines of unsupported record types are skipped and counted, see [GfaReader::skipped].
+///
+/// The reader starts in GFA1 mode and switches to GFA2 when it finds a header with `VN:Z:2.0`,
+/// GFA2 records are mapped to the same [Entry] variants (see the `gfa2` module).
+pub struct GfaReader<R> {
+    reader: R,
+    buffer: String,
+    line_number: usize,
+    gfa2: bool,
+
+    skipped: Vec<(char, usize)>,
+}
+
+impl<R: BufRead> GfaReader<R> {
+    pub fn new(reader: R) -> Self {
+        GfaReader {
+            reader,
+            buffer: String::new(),
+            line_number: 0,
+            gfa2: false,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Number of the last line read, 1-based
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Whether the source declared itself as GFA2 in the header
+    pub fn is_gfa2(&self) -> bool {
+        self.gfa2
+    }
 
-        if line.is_empty() || line.starts_with('#') {
-            continue;
+    /// Skipped lines so far, same consecutive record types are compacted together
+    pub fn skipped(&self) -> &[(char, usize)] {
+        &self.skipped
+    }
+
+    pub fn print_skipped(&self) {
+        for (record_type, count) in self.skipped.iter() {
+            eprintln!("Skipped {} lines of type: {}", count, record_type);
+        }
+    }
+
+    fn skip(&mut self, record_type: char) {
+        match self.skipped.last_mut() {
+            Some((last, count)) if *last == record_type => *count += 1,
+            _ => self.skipped.push((record_type, 1)),
         }
+    }
+}
+
+impl<R: BufRead> Iterator for GfaReader<R> {
+    type Item = Result<Entry, GfaError>;
 
-        let first_char = line.chars().next().unwrap();
-        let entry = match first_char {
-            'H' => parse_header(line),
-            'S' => parse_segment(line),
-            'L' => parse_link(line),
-            // 'P' => parse_path(line),
-            // 'W' => parse_walk(line),
-            _ => {
-                skipped.push(line.chars().next().expect("got empty line"));
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(err.into())),
+            }
+
+            self.line_number += 1;
+
+            let line = self.buffer.trim();
+
+            if line.is_empty() || line.starts_with('#') {
                 continue;
             }
-        };
 
-        entries.push(entry);
+            let record_type = line.chars().next().expect("got empty line");
+
+            match parse_line(line, self.gfa2) {
+                Ok(Some(entry)) => {
+                    if let Entry::Header {
+                        version: Some(version),
+                        ..
+                    } = &entry
+                    {
+                        self.gfa2 = version.starts_with('2');
+                    }
+
+                    return Some(Ok(entry));
+                }
+                Ok(None) => self.skip(record_type),
+                Err(ColumnError { column, reason }) => {
+                    return Some(Err(GfaError::Parse {
+                        line: self.line_number,
+                        record_type,
+                        column: column + 1,
+                        reason,
+                    }))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "H\tVN:Z:1.0
+S\t11\tG
+S\t12\tA
+L\t11\t+\t12\t-\t*
+P\tA\t11+,12-\t4M
+W\tsample\t1\tA\t0\t5\t>11<12
+";
+
+    #[test]
+    fn test_parse_paths_and_walks() {
+        let entries = parse_source(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(entries.len(), 6);
+
+        match &entries[4] {
+            Entry::Path {
+                name,
+                segments,
+                overlaps,
+                ..
+            } => {
+                assert_eq!(name, "A");
+                assert_eq!(
+                    segments,
+                    &vec![
+                        ("11".to_string(), Orientation::Forward),
+                        ("12".to_string(), Orientation::Reverse),
+                    ]
+                );
+                assert_eq!(overlaps, &vec!["4M".to_string()]);
+            }
+            entry => panic!("expected a path, got {:?}", entry),
+        }
+
+        match &entries[5] {
+            Entry::Walk {
+                sample,
+                haplotype_index,
+                seq_id,
+                seq_start,
+                seq_end,
+                segments,
+                ..
+            } => {
+                assert_eq!(sample, "sample");
+                assert_eq!(*haplotype_index, 1);
+                assert_eq!(seq_id, "A");
+                assert_eq!((*seq_start, *seq_end), (0, 5));
+                assert_eq!(
+                    segments,
+                    &vec![
+                        ("11".to_string(), Orientation::Forward),
+                        ("12".to_string(), Orientation::Reverse),
+                    ]
+                );
+            }
+            entry => panic!("expected a walk, got {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let source = "S\t1\tA\nL\t1\t+\t2\t?\t*\n";
+
+        match parse_source(source.as_bytes()) {
+            Err(GfaError::Parse {
+                line,
+                record_type,
+                column,
+                ..
+            }) => {
+                assert_eq!(line, 2);
+                assert_eq!(record_type, 'L');
+                assert_eq!(column, 5);
+            }
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+
+        let source = "S\t1\nS\t2\tA\nW\ts\tx\tA\t0\t5\t>1\n";
+
+        let (entries, errors) = parse_source_lenient(source.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[1].to_string(),
+            "line 3: invalid W record at column 3: invalid haplotype index: \"x\""
+        );
     }
 
-    // Print skipped lines by compacting same ones together
-    for (s, count) in skipped.iter().fold(Vec::new(), |mut acc, s| {
-        if let Some((last, count)) = acc.last_mut() {
-            if *last == s {
-                *count += 1;
-            } else {
-                acc.push((s, 1));
+    #[test]
+    fn test_parse_invalid_tags() {
+        let source = "S\t1\tA\tSN:Z:chr1\tLN:x:4\nL\t1\t+\t1\t-\t*\tRC:i:x\nS\t2\tC\n";
+
+        match parse_source(source.as_bytes()) {
+            Err(GfaError::Parse { line, column, .. }) => {
+                assert_eq!(line, 1);
+                assert_eq!(column, 5);
             }
-        } else {
-            acc.push((s, 1));
+            result => panic!("expected a parse error, got {:?}", result),
         }
 
-        acc
-    }) {
-        eprintln!("Skipped {} lines of type: {}", count, s);
+        // the records with a malformed tag are skipped like any other malformed line
+        let (entries, errors) = parse_source_lenient(source.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| match error {
+                    GfaError::Parse { line, column, .. } => (*line, *column),
+                    error => panic!("expected a parse error, got {:?}", error),
+                })
+                .collect::<Vec<_>>(),
+            vec![(1, 5), (2, 7)]
+        );
     }
 
-    Ok(entries)
+    #[test]
+    fn test_gfa_reader() {
+        let source = format!("{}X\t11\nX\t12\n", EXAMPLE);
+        let mut reader = GfaReader::new(source.as_bytes());
+
+        assert!(matches!(
+            reader.next(),
+            Some(Ok(Entry::Header {
+                version: Some(_),
+                ..
+            }))
+        ));
+        assert_eq!(reader.line_number(), 1);
+
+        assert_eq!(reader.by_ref().filter(|entry| entry.is_ok()).count(), 5);
+        assert_eq!(reader.skipped(), &[('X', 2)]);
+    }
+
+    #[test]
+    fn test_decompressed() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        // two gzip members one after the other, like a bgzip file
+        let mut compressed = Vec::new();
+        for chunk in EXAMPLE.split_inclusive("L\t") {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(chunk.as_bytes()).unwrap();
+            compressed.extend(encoder.finish().unwrap());
+        }
+
+        let entries = parse_source(decompressed(io::Cursor::new(compressed)).unwrap()).unwrap();
+        assert_eq!(entries.len(), 6);
+
+        let entries = parse_source(decompressed(EXAMPLE.as_bytes()).unwrap()).unwrap();
+        assert_eq!(entries.len(), 6);
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        use crate::gfa::tags::{TagArray, TagValue};
+
+        let source = "H\tVN:Z:1.1\tnn:f:0.5
+S\t1\tACGT\tLN:i:4\tSN:Z:chr1\tSO:i:0\tSR:i:0\tzz:B:c,-1,2\tzj:J:{\"a\":[1,2]}\tzh:H:1AFF
+L\t1\t+\t1\t-\t2M\tRC:i:7
+";
+        let entries = parse_source(source.as_bytes()).unwrap();
+
+        assert!(matches!(&entries[0], Entry::Header { version: Some(v), .. } if v == "1.1"));
+        assert_eq!(entries[0].tags().get("nn"), Some(&TagValue::Float(0.5)));
+
+        let tags = entries[1].tags();
+        assert_eq!(tags.get_int("LN"), Some(4));
+        assert_eq!(tags.get_str("SN"), Some("chr1"));
+        assert_eq!(
+            tags.get("zz"),
+            Some(&TagValue::Array(TagArray::Int8(vec![-1, 2])))
+        );
+        assert_eq!(
+            tags.get("zj"),
+            Some(&TagValue::Json("{\"a\":[1,2]}".to_string()))
+        );
+        assert_eq!(tags.get("zh"), Some(&TagValue::ByteArray(vec![0x1a, 0xff])));
+        assert_eq!(
+            tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>()[4..],
+            ["zz:B:c,-1,2", "zj:J:{\"a\":[1,2]}", "zh:H:1AFF"]
+        );
+
+        assert!(matches!(&entries[2], Entry::Link { overlap, .. } if overlap == "2M"));
+        assert_eq!(entries[2].tags().get_int("RC"), Some(7));
+
+        match parse_source("S\t1\tA\tLN:x:4\n".as_bytes()) {
+            Err(GfaError::Parse { column, .. }) => assert_eq!(column, 4),
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_containments_and_jumps() {
+        let source =
+            "C\t1\t+\t2\t-\t12\t4M\tID:Z:c1\nJ\t1\t+\t3\t-\t*\tSC:i:1\nJ\t2\t-\t3\t+\t-5\n";
+        let entries = parse_source(source.as_bytes()).unwrap();
+
+        assert!(matches!(
+            &entries[0],
+            Entry::Containment {
+                contained_orient: Orientation::Reverse,
+                pos: 12,
+                ..
+            }
+        ));
+        assert_eq!(entries[0].tags().get_str("ID"), Some("c1"));
+
+        assert!(matches!(&entries[1], Entry::Jump { distance: None, .. }));
+        assert_eq!(entries[1].tags().get_int("SC"), Some(1));
+        assert!(matches!(
+            &entries[2],
+            Entry::Jump {
+                distance: Some(-5),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_gfa2() {
+        let source = "H\tVN:Z:2.0
+S\t1\t4\tACGT
+S\t2\t3\tAAA\tSN:Z:chr1
+E\t*\t1+\t2+\t2\t4$\t0\t2\t2M
+E\te2\t1+\t2-\t0\t2\t0\t2\t2M
+E\t*\t1+\t2+\t1\t4$\t0\t3$\t*
+E\t*\t1+\t2+\t1\t2\t1\t2\t*
+G\tg1\t1+\t2-\t500\t50
+O\tp1\t1+ 2-
+U\tu1\t1 2
+F\t1\tread1+\t0\t4$\t0\t4\t*
+";
+        let mut reader = GfaReader::new(source.as_bytes());
+        let entries = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert!(reader.is_gfa2());
+        assert_eq!(reader.skipped(), &[('E', 1), ('F', 1)]);
+        assert_eq!(entries.len(), 9);
+
+        assert!(matches!(&entries[1], Entry::Segment { sequence, .. } if sequence == "ACGT"));
+        assert_eq!(entries[1].tags().get_int("LN"), Some(4));
+
+        assert!(matches!(
+            &entries[3],
+            Entry::Link { from, to, to_orient: Orientation::Forward, .. } if from == "1" && to == "2"
+        ));
+        // 2- ends where 1+ starts, so the link goes from 2- to 1+
+        assert!(matches!(
+            &entries[4],
+            Entry::Link { from, from_orient: Orientation::Reverse, to, .. } if from == "2" && to == "1"
+        ));
+        assert_eq!(entries[4].tags().get_str("ID"), Some("e2"));
+        assert!(matches!(
+            &entries[5],
+            Entry::Containment { container, contained, pos: 1, .. } if container == "1" && contained == "2"
+        ));
+
+        assert!(matches!(
+            &entries[6],
+            Entry::Jump {
+                distance: Some(500),
+                ..
+            }
+        ));
+        assert_eq!(entries[6].tags().get_int("VR"), Some(50));
+
+        assert!(matches!(&entries[7], Entry::Path { segments, .. } if segments.len() == 2));
+        assert!(matches!(&entries[8], Entry::Set { members, .. } if members == &["1", "2"]));
+    }
 }
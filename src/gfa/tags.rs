@@ -0,0 +1,240 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Value of an optional field, the variants follow the type letters of the GFA specification
+///
+/// ```txt
+/// LN:i:42  SN:Z:chr1  xx:B:i,1,2,3  xy:J:{"a":1}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+    /// `A`, a single printable character
+    Char(char),
+    /// `i`, a signed integer
+    Int(i64),
+    /// `f`, a floating point number
+    Float(f64),
+    /// `Z`, a printable string
+    String(String),
+    /// `J`, a JSON value, kept as the raw string
+    Json(String),
+    /// `H`, a byte array encoded in hex
+    ByteArray(Vec<u8>),
+    /// `B`, an array of integers or floats
+    Array(TagArray),
+}
+
+/// Numeric array of a `B` field, the variant follows the subtype letter
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagArray {
+    Int8(Vec<i8>),
+    UInt8(Vec<u8>),
+    Int16(Vec<i16>),
+    UInt16(Vec<u16>),
+    Int32(Vec<i32>),
+    UInt32(Vec<u32>),
+    Float(Vec<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub value: TagValue,
+}
+
+/// Optional fields of a GFA record, in the order they appear in the line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tags(Vec<Tag>);
+
+impl Tags {
+    pub fn new() -> Self {
+        Tags(Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Tag> {
+        self.0.iter()
+    }
+
+    /// Sets the value of a tag, replacing the previous one with the same name if present
+    pub fn insert(&mut self, name: &str, value: TagValue) {
+        match self.0.iter_mut().find(|tag| tag.name == name) {
+            Some(tag) => tag.value = value,
+            None => self.0.push(Tag {
+                name: name.to_string(),
+                value,
+            }),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TagValue> {
+        self.0
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| &tag.value)
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            TagValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            TagValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FromIterator<Tag> for Tags {
+    fn from_iter<I: IntoIterator<Item = Tag>>(iter: I) -> Self {
+        Tags(iter.into_iter().collect())
+    }
+}
+
+fn parse_numbers<T: FromStr>(values: &[&str]) -> Result<Vec<T>, String> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid array value: {:?}", value))
+        })
+        .collect()
+}
+
+fn parse_array(s: &str) -> Result<TagArray, String> {
+    let mut values = s.split(',');
+    let subtype = values.next().unwrap_or("");
+    let values = values.collect::<Vec<_>>();
+
+    Ok(match subtype {
+        "c" => TagArray::Int8(parse_numbers(&values)?),
+        "C" => TagArray::UInt8(parse_numbers(&values)?),
+        "s" => TagArray::Int16(parse_numbers(&values)?),
+        "S" => TagArray::UInt16(parse_numbers(&values)?),
+        "i" => TagArray::Int32(parse_numbers(&values)?),
+        "I" => TagArray::UInt32(parse_numbers(&values)?),
+        "f" => TagArray::Float(parse_numbers(&values)?),
+        _ => return Err(format!("invalid array subtype: {:?}", subtype)),
+    })
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(format!("invalid byte array: {:?}", s));
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| format!("invalid byte array: {:?}", s))
+        })
+        .collect()
+}
+
+impl FromStr for Tag {
+    type Err = String;
+
+    /// Parses a single optional field of the form `XX:T:value`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+
+        let (name, kind, value) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(name), Some(kind), Some(value)) => (name, kind, value),
+            _ => return Err(format!("invalid tag: {:?}", s)),
+        };
+
+        let mut name_chars = name.chars();
+        let valid_name = name.len() == 2
+            && name_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && name_chars.next().is_some_and(|c| c.is_ascii_alphanumeric());
+
+        if !valid_name {
+            return Err(format!("invalid tag name: {:?}", name));
+        }
+
+        let value = match kind {
+            "A" => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => TagValue::Char(c),
+                    _ => return Err(format!("invalid char value: {:?}", value)),
+                }
+            }
+            "i" => TagValue::Int(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid integer value: {:?}", value))?,
+            ),
+            "f" => TagValue::Float(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid float value: {:?}", value))?,
+            ),
+            "Z" => TagValue::String(value.to_string()),
+            "J" => TagValue::Json(value.to_string()),
+            "H" => TagValue::ByteArray(parse_hex(value)?),
+            "B" => TagValue::Array(parse_array(value)?),
+            _ => return Err(format!("invalid tag type: {:?}", kind)),
+        };
+
+        Ok(Tag {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+fn write_numbers<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    subtype: char,
+    values: &[T],
+) -> std::fmt::Result {
+    write!(f, "{}", subtype)?;
+    for value in values {
+        write!(f, ",{}", value)?;
+    }
+
+    Ok(())
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.name)?;
+
+        match &self.value {
+            TagValue::Char(value) => write!(f, "A:{}", value),
+            TagValue::Int(value) => write!(f, "i:{}", value),
+            TagValue::Float(value) => write!(f, "f:{}", value),
+            TagValue::String(value) => write!(f, "Z:{}", value),
+            TagValue::Json(value) => write!(f, "J:{}", value),
+            TagValue::ByteArray(bytes) => {
+                write!(f, "H:")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+
+                Ok(())
+            }
+            TagValue::Array(array) => {
+                write!(f, "B:")?;
+
+                match array {
+                    TagArray::Int8(values) => write_numbers(f, 'c', values),
+                    TagArray::UInt8(values) => write_numbers(f, 'C', values),
+                    TagArray::Int16(values) => write_numbers(f, 's', values),
+                    TagArray::UInt16(values) => write_numbers(f, 'S', values),
+                    TagArray::Int32(values) => write_numbers(f, 'i', values),
+                    TagArray::UInt32(values) => write_numbers(f, 'I', values),
+                    TagArray::Float(values) => write_numbers(f, 'f', values),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::{
+    gfa::{
+        tags::{TagValue, Tags},
+        Connection, Entry, Orientation,
+    },
+    graph::{unitigs::Unitig, BidirectedGraph, Graph, Side},
+};
+
+/// Formats the tags of an entry as trailing columns
+struct TagColumns<'a>(&'a Tags);
+
+impl Display for TagColumns<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for tag in self.0.iter() {
+            write!(f, "\t{}", tag)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats an oriented segment as a path step, `11+` for paths and `>11` for walks
+fn path_step(name: &str, orient: Orientation, walk: bool) -> String {
+    match (walk, orient) {
+        (false, _) => format!("{}{}", name, orient),
+        (true, Orientation::Forward) => format!(">{}", name),
+        (true, Orientation::Reverse) => format!("<{}", name),
+    }
+}
+
+impl Display for Entry {
+    /// Formats the entry as a GFA 1.x line without the trailing newline, unordered groups have
+    /// no GFA1 equivalent and are formatted as comments
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Entry::Header { version, tags } => {
+                write!(f, "H")?;
+
+                if let (Some(version), None) = (version, tags.get("VN")) {
+                    write!(f, "\tVN:Z:{}", version)?;
+                }
+
+                write!(f, "{}", TagColumns(tags))
+            }
+            Entry::Segment { id, sequence, tags } => {
+                write!(f, "S\t{}\t{}{}", id, sequence, TagColumns(tags))
+            }
+            Entry::Link {
+                from,
+                from_orient,
+                to,
+                to_orient,
+                overlap,
+                tags,
+            } => write!(
+                f,
+                "L\t{}\t{}\t{}\t{}\t{}{}",
+                from,
+                from_orient,
+                to,
+                to_orient,
+                overlap,
+                TagColumns(tags)
+            ),
+            Entry::Path {
+                name,
+                segments,
+                overlaps,
+                tags,
+            } => {
+                let steps = segments
+                    .iter()
+                    .map(|(name, orient)| path_step(name, *orient, false))
+                    .collect::<Vec<_>>();
+
+                let overlaps = if overlaps.is_empty() {
+                    "*".to_string()
+                } else {
+                    overlaps.join(",")
+                };
+
+                write!(
+                    f,
+                    "P\t{}\t{}\t{}{}",
+                    name,
+                    steps.join(","),
+                    overlaps,
+                    TagColumns(tags)
+                )
+            }
+            Entry::Walk {
+                sample,
+                haplotype_index,
+                seq_id,
+                seq_start,
+                seq_end,
+                segments,
+                tags,
+            } => {
+                let steps = segments
+                    .iter()
+                    .map(|(name, orient)| path_step(name, *orient, true))
+                    .collect::<String>();
+
+                write!(
+                    f,
+                    "W\t{}\t{}\t{}\t{}\t{}\t{}{}",
+                    sample,
+                    haplotype_index,
+                    seq_id,
+                    seq_start,
+                    seq_end,
+                    steps,
+                    TagColumns(tags)
+                )
+            }
+            Entry::Containment {
+                container,
+                container_orient,
+                contained,
+                contained_orient,
+                pos,
+                overlap,
+                tags,
+            } => write!(
+                f,
+                "C\t{}\t{}\t{}\t{}\t{}\t{}{}",
+                container,
+                container_orient,
+                contained,
+                contained_orient,
+                pos,
+                overlap,
+                TagColumns(tags)
+            ),
+            Entry::Jump {
+                from,
+                from_orient,
+                to,
+                to_orient,
+                distance,
+                tags,
+            } => {
+                let distance = match distance {
+                    Some(distance) => distance.to_string(),
+                    None => "*".to_string(),
+                };
+
+                write!(
+                    f,
+                    "J\t{}\t{}\t{}\t{}\t{}{}",
+                    from,
+                    from_orient,
+                    to,
+                    to_orient,
+                    distance,
+                    TagColumns(tags)
+                )
+            }
+            Entry::Set { name, members, .. } => {
+                write!(f, "# U\t{}\t{}", name, members.join(" "))
+            }
+        }
+    }
+}
+
+/// Edge payloads that can be written as the overlap of a link
+pub trait LinkOverlap {
+    fn overlap(&self) -> &str;
+
+    /// Line written for an edge with this payload, a link by default
+    fn entry(
+        &self,
+        from: String,
+        from_orient: Orientation,
+        to: String,
+        to_orient: Orientation,
+    ) -> Entry {
+        Entry::Link {
+            from,
+            from_orient,
+            to,
+            to_orient,
+            overlap: self.overlap().to_string(),
+            tags: Tags::new(),
+        }
+    }
+}
+
+impl LinkOverlap for () {
+    fn overlap(&self) -> &str {
+        "*"
+    }
+}
+
+/// The CIGAR string of the overlap, empty if unknown
+impl LinkOverlap for String {
+    fn overlap(&self) -> &str {
+        if self.is_empty() {
+            "*"
+        } else {
+            self
+        }
+    }
+}
+
+/// Links are written with their overlap and jumps as `J` lines with their distance
+impl LinkOverlap for Connection {
+    fn overlap(&self) -> &str {
+        match self {
+            Connection::Link(cigar) => cigar.overlap(),
+            Connection::Jump(_) => "*",
+        }
+    }
+
+    fn entry(
+        &self,
+        from: String,
+        from_orient: Orientation,
+        to: String,
+        to_orient: Orientation,
+    ) -> Entry {
+        match self {
+            Connection::Link(cigar) => cigar.entry(from, from_orient, to, to_orient),
+            Connection::Jump(distance) => Entry::Jump {
+                from,
+                from_orient,
+                to,
+                to_orient,
+                distance: *distance,
+                tags: Tags::new(),
+            },
+        }
+    }
+}
+
+pub fn write_entry<W: Write>(out: &mut W, entry: &Entry) -> io::Result<()> {
+    writeln!(out, "{}", entry)
+}
+
+pub fn write_entries<'a, W, I>(out: &mut W, entries: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Entry>,
+{
+    for entry in entries {
+        write_entry(out, entry)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a graph with `(segment id, orientation)` nodes as GFA 1.0, with a segment line for
+/// every segment appearing in the graph and a line for every edge (see [LinkOverlap::entry]),
+/// the two edges of a link walked on both strands are written once. The overlaps are taken
+/// from the edge payloads, "*" if not known.
+pub fn write_graph<W, E>(
+    out: &mut W,
+    graph: &impl Graph<(String, Orientation), E>,
+    sequence_map: &HashMap<String, String>,
+) -> io::Result<()>
+where
+    W: Write,
+    E: LinkOverlap + Clone + Default,
+{
+    let segments = graph
+        .nodes_iter()
+        .map(|(id, _)| id)
+        .collect::<BTreeSet<_>>();
+
+    write_entry(
+        out,
+        &Entry::Header {
+            version: Some("1.0".to_string()),
+            tags: Tags::new(),
+        },
+    )?;
+
+    for id in segments {
+        let sequence = sequence_map.get(id).map(String::as_str).unwrap_or("*");
+
+        writeln!(out, "S\t{}\t{}", id, sequence)?;
+    }
+
+    for from in graph.nodes_iter() {
+        for (to, payload) in graph.out_edges_iter(from) {
+            // a link can be walked on both strands, if the graph has both edges only the
+            // smallest one is written
+            let twin = ((to.0.clone(), to.1.flip()), (from.0.clone(), from.1.flip()));
+            if (&twin.0, &twin.1) < (from, to) && graph.edge(&twin.0, &twin.1).is_some() {
+                continue;
+            }
+
+            write_entry(
+                out,
+                &payload.entry(from.0.clone(), from.1, to.0.clone(), to.1),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes compacted unitigs as GFA 1.0, unitig `i` is named `i + 1` and its `sg` tag lists the
+/// original segments it was merged from, like a path (`11+,12-`)
+pub fn write_unitigs<W, E>(
+    out: &mut W,
+    unitigs: &[Unitig<String>],
+    graph: &BidirectedGraph<usize, E>,
+) -> io::Result<()>
+where
+    W: Write,
+    E: LinkOverlap + Clone + Default,
+{
+    write_entry(
+        out,
+        &Entry::Header {
+            version: Some("1.0".to_string()),
+            tags: Tags::new(),
+        },
+    )?;
+
+    for (i, unitig) in unitigs.iter().enumerate() {
+        let segments = unitig
+            .segments
+            .iter()
+            .map(|(name, orient)| path_step(name, *orient, false))
+            .collect::<Vec<_>>();
+
+        let mut tags = Tags::new();
+        tags.insert("sg", TagValue::String(segments.join(",")));
+
+        write_entry(
+            out,
+            &Entry::Segment {
+                id: (i + 1).to_string(),
+                sequence: unitig.sequence.clone(),
+                tags,
+            },
+        )?;
+    }
+
+    for ((from, from_side), (to, to_side)) in graph.edges() {
+        // the link leaves `from` from `from_side` and enters `to` from `to_side`
+        let from_orient = match from_side {
+            Side::End => Orientation::Forward,
+            Side::Start => Orientation::Reverse,
+        };
+        let to_orient = match to_side {
+            Side::Start => Orientation::Forward,
+            Side::End => Orientation::Reverse,
+        };
+
+        let payload = graph
+            .link(&from, from_orient, &to, to_orient)
+            .cloned()
+            .unwrap_or_default();
+
+        write_entry(
+            out,
+            &payload.entry(
+                (from + 1).to_string(),
+                from_orient,
+                (to + 1).to_string(),
+                to_orient,
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gfa::{parser::parse_source, reverse_overlap},
+        graph::AdjacencyGraph,
+    };
+
+    #[test]
+    fn test_write_entries_roundtrip() {
+        let source = "H\tVN:Z:1.0
+S\t11\tG\tLN:i:1
+S\t12\tA
+L\t11\t+\t12\t-\t*\tRC:i:3
+P\tA\t11+,12-\t*
+W\tsample\t1\tA\t0\t5\t>11<12
+C\t11\t+\t12\t-\t0\t1M
+J\t11\t+\t12\t+\t*
+";
+        let entries = parse_source(source.as_bytes()).unwrap();
+
+        let mut out = Vec::new();
+        write_entries(&mut out, &entries).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), source);
+    }
+
+    #[test]
+    fn test_write_graph() {
+        let mut graph: AdjacencyGraph<_> = AdjacencyGraph::new();
+        graph.add_edge(
+            ("1".to_string(), Orientation::Forward),
+            ("2".to_string(), Orientation::Reverse),
+        );
+
+        let sequence_map = HashMap::from([
+            ("1".to_string(), "AC".to_string()),
+            ("2".to_string(), "GT".to_string()),
+        ]);
+
+        let mut out = Vec::new();
+        write_graph(&mut out, &graph, &sequence_map).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tAC\nS\t2\tGT\nL\t1\t+\t2\t-\t*\n"
+        );
+
+        let mut graph: AdjacencyGraph<_, String> = AdjacencyGraph::new();
+        graph.add_edge_with(
+            ("1".to_string(), Orientation::Forward),
+            ("2".to_string(), Orientation::Forward),
+            "1M".to_string(),
+        );
+        graph.add_edge(
+            ("2".to_string(), Orientation::Forward),
+            ("1".to_string(), Orientation::Forward),
+        );
+
+        let mut out = Vec::new();
+        write_graph(&mut out, &graph, &sequence_map).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tAC\nS\t2\tGT\nL\t1\t+\t2\t+\t1M\nL\t2\t+\t1\t+\t*\n"
+        );
+    }
+
+    #[test]
+    fn test_write_graph_both_strands() {
+        let source = "H\tVN:Z:1.0\nS\t1\tAC\nS\t2\tGT\nL\t1\t+\t2\t+\t1M\nL\t2\t+\t1\t-\t*\n";
+
+        let mut bidirected: BidirectedGraph<String, String> = BidirectedGraph::new();
+        for entry in parse_source(source.as_bytes()).unwrap() {
+            if let Entry::Link {
+                from,
+                from_orient,
+                to,
+                to_orient,
+                overlap,
+                ..
+            } = entry
+            {
+                let twin = reverse_overlap(&overlap);
+                bidirected.add_link_with_twin(from, from_orient, to, to_orient, overlap, twin);
+            }
+        }
+
+        let graph = bidirected.to_directed();
+        assert_eq!(graph.edge_count(), 4);
+
+        let sequence_map = HashMap::from([
+            ("1".to_string(), "AC".to_string()),
+            ("2".to_string(), "GT".to_string()),
+        ]);
+
+        // every link is written once even if the graph has an edge for each strand
+        let mut out = Vec::new();
+        write_graph(&mut out, &graph, &sequence_map).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tAC\nS\t2\tGT\nL\t1\t+\t2\t+\t1M\nL\t1\t+\t2\t-\t*\n"
+        );
+    }
+
+    #[test]
+    fn test_write_unitigs() {
+        let unitigs = vec![
+            Unitig {
+                segments: vec![
+                    ("1".to_string(), Orientation::Forward),
+                    ("2".to_string(), Orientation::Reverse),
+                ],
+                sequence: "ACCA".to_string(),
+            },
+            Unitig {
+                segments: vec![("3".to_string(), Orientation::Forward)],
+                sequence: "GT".to_string(),
+            },
+        ];
+
+        let mut graph: BidirectedGraph<_, String> = BidirectedGraph::new();
+        graph.add_link_with(
+            0,
+            Orientation::Forward,
+            1,
+            Orientation::Reverse,
+            "1M".to_string(),
+        );
+
+        let mut out = Vec::new();
+        write_unitigs(&mut out, &unitigs, &graph).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tACCA\tsg:Z:1+,2-\nS\t2\tGT\tsg:Z:3+\nL\t1\t+\t2\t-\t1M\n"
+        );
+
+        // jumps are written as J lines, not as links
+        let mut graph: BidirectedGraph<_, Connection> = BidirectedGraph::new();
+        graph.add_link_with(
+            0,
+            Orientation::Forward,
+            1,
+            Orientation::Forward,
+            Connection::Jump(Some(50)),
+        );
+        graph.add_link_with(
+            1,
+            Orientation::Forward,
+            0,
+            Orientation::Forward,
+            Connection::Link("1M".to_string()),
+        );
+
+        let mut out = Vec::new();
+        write_unitigs(&mut out, &unitigs, &graph).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tACCA\tsg:Z:1+,2-\nS\t2\tGT\tsg:Z:3+\n\
+             L\t1\t-\t2\t-\t1M\nJ\t1\t+\t2\t+\t50\n"
+        );
+    }
+}
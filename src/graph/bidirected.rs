@@ -0,0 +1,281 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use crate::gfa::Orientation;
+
+use super::{AdjacencyGraph, BidirectedGraph, Graph, Side};
+
+impl Side {
+    /// Side a segment is left from when traversed with the given orientation
+    pub fn exit(orient: Orientation) -> Side {
+        match orient {
+            Orientation::Forward => Side::End,
+            Orientation::Reverse => Side::Start,
+        }
+    }
+
+    /// Side a segment is entered from when traversed with the given orientation
+    pub fn entry(orient: Orientation) -> Side {
+        match orient {
+            Orientation::Forward => Side::Start,
+            Orientation::Reverse => Side::End,
+        }
+    }
+}
+
+impl<V, E> BidirectedGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    pub fn new() -> Self {
+        BidirectedGraph {
+            nodes: BTreeSet::new(),
+            adjacencies: BTreeMap::new(),
+        }
+    }
+
+    pub fn nodes(&self) -> &BTreeSet<V> {
+        &self.nodes
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Number of edges, each link is counted once even if it is stored from both sides
+    pub fn edge_count(&self) -> usize {
+        self.edges().len()
+    }
+
+    /// Edges between sides, each one is returned once with its smallest side first
+    pub fn edges(&self) -> BTreeSet<((V, Side), (V, Side))> {
+        self.adjacencies
+            .iter()
+            .flat_map(|(from, tos)| {
+                tos.keys()
+                    .filter(move |to| from <= *to)
+                    .map(move |to| (from.clone(), to.clone()))
+            })
+            .collect()
+    }
+
+    pub fn add_node(&mut self, node: V) {
+        self.nodes.insert(node);
+    }
+
+    /// Adds the link of a GFA `L` line, the link can then be walked as written or on the
+    /// opposite strand (from `to` with flipped orientation to `from` with flipped orientation)
+    pub fn add_link(&mut self, from: V, from_orient: Orientation, to: V, to_orient: Orientation) {
+        let from_side = (from, Side::exit(from_orient));
+        let to_side = (to, Side::entry(to_orient));
+
+        self.add_edge(from_side, to_side);
+    }
+
+    /// Like [BidirectedGraph::add_link] but with a payload, the same payload is used on both
+    /// strands
+    pub fn add_link_with(
+        &mut self,
+        from: V,
+        from_orient: Orientation,
+        to: V,
+        to_orient: Orientation,
+        payload: E,
+    ) {
+        self.add_link_with_twin(from, from_orient, to, to_orient, payload.clone(), payload);
+    }
+
+    /// Like [BidirectedGraph::add_link_with] but `twin` is used when the link is walked on the
+    /// opposite strand, e.g. the overlap as seen from `to`
+    pub fn add_link_with_twin(
+        &mut self,
+        from: V,
+        from_orient: Orientation,
+        to: V,
+        to_orient: Orientation,
+        payload: E,
+        twin: E,
+    ) {
+        let from_side = (from, Side::exit(from_orient));
+        let to_side = (to, Side::entry(to_orient));
+
+        self.add_edge_with_twin(from_side, to_side, payload, twin);
+    }
+
+    /// Adds an edge with the default payload, the payload of an already present edge is kept
+    pub fn add_edge(&mut self, a: (V, Side), b: (V, Side)) {
+        if !self
+            .adjacencies
+            .get(&a)
+            .is_some_and(|sides| sides.contains_key(&b))
+        {
+            self.add_edge_with(a, b, E::default());
+        }
+    }
+
+    pub fn add_edge_with(&mut self, a: (V, Side), b: (V, Side), payload: E) {
+        self.add_edge_with_twin(a, b, payload.clone(), payload);
+    }
+
+    /// Adds an edge with `payload` when walked from `a` to `b` and `twin` when walked from `b`
+    /// to `a`
+    pub fn add_edge_with_twin(&mut self, a: (V, Side), b: (V, Side), payload: E, twin: E) {
+        self.nodes.insert(a.0.clone());
+        self.nodes.insert(b.0.clone());
+
+        self.adjacencies
+            .entry(a.clone())
+            .or_default()
+            .insert(b.clone(), payload);
+        self.adjacencies.entry(b).or_default().insert(a, twin);
+    }
+
+    /// Payload of the link of a GFA `L` line, if present
+    pub fn link(
+        &self,
+        from: &V,
+        from_orient: Orientation,
+        to: &V,
+        to_orient: Orientation,
+    ) -> Option<&E> {
+        self.adjacencies
+            .get(&(from.clone(), Side::exit(from_orient)))?
+            .get(&(to.clone(), Side::entry(to_orient)))
+    }
+
+    pub fn has_link(
+        &self,
+        from: &V,
+        from_orient: Orientation,
+        to: &V,
+        to_orient: Orientation,
+    ) -> bool {
+        self.link(from, from_orient, to, to_orient).is_some()
+    }
+
+    pub fn remove_edge(&mut self, a: &(V, Side), b: &(V, Side)) {
+        if let Some(sides) = self.adjacencies.get_mut(a) {
+            sides.remove(b);
+        }
+
+        if let Some(sides) = self.adjacencies.get_mut(b) {
+            sides.remove(a);
+        }
+    }
+
+    pub fn remove_node(&mut self, node: &V) {
+        self.nodes.remove(node);
+
+        for side in [Side::Start, Side::End] {
+            let key = (node.clone(), side);
+
+            if let Some(others) = self.adjacencies.remove(&key) {
+                for other in others.keys() {
+                    if let Some(sides) = self.adjacencies.get_mut(other) {
+                        sides.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sides connected to the given side
+    pub fn side_neighbors(&self, side: &(V, Side)) -> impl Iterator<Item = &(V, Side)> {
+        self.adjacencies
+            .get(side)
+            .into_iter()
+            .flat_map(|sides| sides.keys())
+    }
+
+    /// Oriented nodes that can follow `node` in a walk, with the payload of the link to them
+    pub fn out_links(
+        &self,
+        node: &(V, Orientation),
+    ) -> impl Iterator<Item = ((V, Orientation), &E)> {
+        let (id, orient) = node;
+
+        self.adjacencies
+            .get(&(id.clone(), Side::exit(*orient)))
+            .into_iter()
+            .flatten()
+            .map(|((other, side), payload)| {
+                let orient = match side {
+                    Side::Start => Orientation::Forward,
+                    Side::End => Orientation::Reverse,
+                };
+
+                ((other.clone(), orient), payload)
+            })
+    }
+
+    /// Oriented nodes that can follow `node` in a walk
+    pub fn neighbors(&self, node: &(V, Orientation)) -> Vec<(V, Orientation)> {
+        self.out_links(node).map(|(adj, _)| adj).collect()
+    }
+
+    /// Oriented nodes that can precede `node` in a walk
+    pub fn predecessors(&self, node: &(V, Orientation)) -> Vec<(V, Orientation)> {
+        let (id, orient) = node;
+
+        self.neighbors(&(id.clone(), orient.flip()))
+            .into_iter()
+            .map(|(other, orient)| (other, orient.flip()))
+            .collect()
+    }
+
+    /// Oriented nodes reachable from `start` walking the links on both strands
+    pub fn reachable_from(&self, start: &(V, Orientation)) -> BTreeSet<(V, Orientation)> {
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![start.clone()];
+
+        while let Some(node) = stack.pop() {
+            if visited.contains(&node) {
+                continue;
+            }
+
+            for adj in self.neighbors(&node) {
+                if !visited.contains(&adj) {
+                    stack.push(adj);
+                }
+            }
+
+            visited.insert(node);
+        }
+
+        visited
+    }
+
+    /// Expands the graph to a directed graph on oriented nodes, each link becomes two edges, one
+    /// for each strand, with the payload of the link as walked on that strand. Nodes without
+    /// links are kept in both orientations.
+    pub fn to_directed(&self) -> AdjacencyGraph<(V, Orientation), E> {
+        let mut graph = AdjacencyGraph::new();
+
+        for node in self.nodes.iter() {
+            for orient in [Orientation::Forward, Orientation::Reverse] {
+                let from = (node.clone(), orient);
+
+                for (to, payload) in self.out_links(&from) {
+                    graph.add_edge_with(from.clone(), to, payload.clone());
+                }
+
+                graph.add_node(from);
+            }
+        }
+
+        graph
+    }
+}
+
+impl<V, E> Default for BidirectedGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use super::{DirectedAcyclicGraph, Graph};
+
+/// Superbubble of a DAG, a subgraph where every path from `entrance` reaches `exit`, every path
+/// to `exit` comes from `entrance` and no smaller subgraph has the same property. Variants like
+/// SNPs and indels show up as superbubbles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Superbubble<V> {
+    pub entrance: V,
+    pub exit: V,
+    /// Nodes strictly between the entrance and the exit
+    pub inside: Vec<V>,
+    /// Index of the smallest superbubble containing this one
+    pub parent: Option<usize>,
+    /// Number of superbubbles containing this one
+    pub depth: usize,
+}
+
+impl<V> Superbubble<V> {
+    /// Number of nodes, including the entrance and the exit
+    pub fn size(&self) -> usize {
+        self.inside.len() + 2
+    }
+}
+
+impl<V, E> DirectedAcyclicGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Finds all superbubbles with at least one node inside, sorted by entrance, and their
+    /// nesting tree. This checks every node with more than one out-neighbor as an entrance
+    /// using the algorithm of Onodera et al. (2013), so it is O(|V| |E|) in the worst case.
+    pub fn superbubbles(&self) -> Vec<Superbubble<V>> {
+        let in_degrees = self.in_degrees();
+
+        let mut bubbles = self
+            .nodes_iter()
+            .filter(|entrance| self.out_degree(entrance) > 1)
+            .filter_map(|entrance| {
+                let (exit, inside) = self.superbubble_exit(entrance, &in_degrees)?;
+
+                Some(Superbubble {
+                    entrance: entrance.clone(),
+                    exit: exit.clone(),
+                    inside: inside.into_iter().cloned().collect(),
+                    parent: None,
+                    depth: 0,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // superbubbles are either nested or share at most an endpoint, so the parent of a
+        // bubble is the smallest one with its entrance inside
+        let mut by_size = (0..bubbles.len()).collect::<Vec<_>>();
+        by_size.sort_by_key(|i| std::cmp::Reverse(bubbles[*i].inside.len()));
+
+        let mut smallest_containing: BTreeMap<V, usize> = BTreeMap::new();
+
+        for i in by_size {
+            if let Some(parent) = smallest_containing.get(&bubbles[i].entrance).copied() {
+                bubbles[i].parent = Some(parent);
+                bubbles[i].depth = bubbles[parent].depth + 1;
+            }
+
+            for node in bubbles[i].inside.iter() {
+                smallest_containing.insert(node.clone(), i);
+            }
+        }
+
+        bubbles
+    }
+
+    /// Returns the exit of the superbubble starting at `entrance` and the nodes inside, if
+    /// there is one
+    fn superbubble_exit<'a>(
+        &'a self,
+        entrance: &'a V,
+        in_degrees: &BTreeMap<&V, usize>,
+    ) -> Option<(&'a V, Vec<&'a V>)> {
+        // parents not visited yet of the seen nodes
+        let mut remaining: BTreeMap<&V, usize> = BTreeMap::new();
+        let mut seen = BTreeSet::new();
+        let mut inside = Vec::new();
+        let mut stack = vec![entrance];
+
+        while let Some(node) = stack.pop() {
+            seen.remove(node);
+
+            if node != entrance {
+                inside.push(node);
+            }
+
+            // a tip, paths from the entrance end before reaching an exit
+            if self.out_degree(node) == 0 {
+                return None;
+            }
+
+            for adj in self.neighbors_iter(node) {
+                if adj == entrance {
+                    return None;
+                }
+
+                seen.insert(adj);
+
+                let parents = remaining
+                    .entry(adj)
+                    .or_insert_with(|| in_degrees.get(adj).copied().unwrap_or(0));
+                *parents -= 1;
+
+                if *parents == 0 {
+                    stack.push(adj);
+                }
+            }
+
+            if stack.len() == 1 && seen.len() == 1 {
+                return Some((stack.pop()?, inside));
+            }
+        }
+
+        None
+    }
+}
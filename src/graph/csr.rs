@@ -0,0 +1,414 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+use crate::gfa::Orientation;
+
+use super::{AdjacencyGraph, BidirectedGraph, CsrGraph, Graph, Side};
+
+/// Largest segment index, the lowest bit of a [handle] is taken by the orientation
+pub const MAX_SEGMENT: u32 = u32::MAX >> 1;
+
+/// Table of interned segment ids, each id gets a dense `u32` index in insertion order, up to
+/// [MAX_SEGMENT]
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    ids: Vec<Box<str>>,
+    indices: HashMap<Box<str>, u32>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the index of the id, adding it to the table if not already present
+    pub fn intern(&mut self, id: &str) -> u32 {
+        if let Some(index) = self.indices.get(id) {
+            return *index;
+        }
+
+        let index = u32::try_from(self.ids.len())
+            .ok()
+            .filter(|index| *index <= MAX_SEGMENT)
+            .expect("too many ids for a u32 handle");
+        self.ids.push(id.into());
+        self.indices.insert(id.into(), index);
+
+        index
+    }
+
+    pub fn get(&self, id: &str) -> Option<u32> {
+        self.indices.get(id).copied()
+    }
+
+    pub fn resolve(&self, index: u32) -> &str {
+        &self.ids[index as usize]
+    }
+}
+
+/// Handle of an oriented segment, the lowest bit is the orientation. The segment must be at most
+/// [MAX_SEGMENT], as the indices of an [Interner] are.
+pub fn handle(segment: u32, orient: Orientation) -> u32 {
+    assert!(
+        segment <= MAX_SEGMENT,
+        "segment {} has no u32 handle",
+        segment
+    );
+
+    match orient {
+        Orientation::Forward => segment << 1,
+        Orientation::Reverse => segment << 1 | 1,
+    }
+}
+
+/// Inverse of [handle]
+pub fn unpack_handle(handle: u32) -> (u32, Orientation) {
+    let orient = if handle & 1 == 0 {
+        Orientation::Forward
+    } else {
+        Orientation::Reverse
+    };
+
+    (handle >> 1, orient)
+}
+
+impl CsrGraph {
+    /// Builds the graph from a list of edges with a counting sort, duplicated edges are merged.
+    /// All handles below `node_count` are nodes of the graph.
+    pub fn with_edges<I>(node_count: usize, edges: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, u32)>,
+    {
+        let edges = edges.into_iter().collect::<Vec<_>>();
+
+        let node_count = edges
+            .iter()
+            .map(|(from, to)| *from.max(to) as usize + 1)
+            .max()
+            .unwrap_or(0)
+            .max(node_count);
+
+        let mut offsets = vec![0; node_count + 1];
+        for (from, _) in edges.iter() {
+            offsets[*from as usize + 1] += 1;
+        }
+        for i in 0..node_count {
+            offsets[i + 1] += offsets[i];
+        }
+
+        let mut targets = vec![0; edges.len()];
+        let mut next = offsets.clone();
+        for (from, to) in edges {
+            targets[next[from as usize]] = to;
+            next[from as usize] += 1;
+        }
+
+        // sort and deduplicate each row, compacting the targets
+        let mut compacted = Vec::with_capacity(targets.len());
+        let mut compacted_offsets = Vec::with_capacity(offsets.len());
+        compacted_offsets.push(0);
+
+        for i in 0..node_count {
+            let row = &mut targets[offsets[i]..offsets[i + 1]];
+            row.sort_unstable();
+
+            let start = compacted.len();
+            for to in row.iter() {
+                if compacted.len() == start || compacted.last() != Some(to) {
+                    compacted.push(*to);
+                }
+            }
+
+            compacted_offsets.push(compacted.len());
+        }
+
+        compacted.shrink_to_fit();
+
+        CsrGraph {
+            offsets: compacted_offsets,
+            targets: compacted,
+            nodes: (0..node_count as u32).collect(),
+        }
+    }
+
+    /// Keeps as nodes of the graph only the handles that are endpoints of some edge, like
+    /// graphs built by calling [Graph::add_edge] for each edge
+    fn retain_edge_endpoints(&mut self) {
+        let mut endpoints = vec![false; self.handle_count()];
+
+        for from in 0..self.handle_count() {
+            for to in self.targets[self.offsets[from]..self.offsets[from + 1]].iter() {
+                endpoints[from] = true;
+                endpoints[*to as usize] = true;
+            }
+        }
+
+        self.nodes = (0..self.handle_count() as u32)
+            .filter(|node| endpoints[*node as usize])
+            .collect();
+    }
+
+    /// Builds the graph on oriented segments of a bidirected graph, with the same nodes and
+    /// edges as [BidirectedGraph::to_directed] but without copying the segment ids in every node
+    /// and edge. Returns the table to map segment indices back to ids, node handles are built
+    /// with [handle].
+    pub fn from_bidirected<E>(graph: &BidirectedGraph<String, E>) -> (Self, Interner)
+    where
+        E: Clone + Default,
+    {
+        let mut interner = Interner::new();
+
+        for id in graph.nodes.iter() {
+            interner.intern(id);
+        }
+
+        // every link is stored from both its sides, so each entry is the edge of one strand
+        let edges = graph
+            .adjacencies
+            .iter()
+            .flat_map(|((from, from_side), tos)| {
+                let from_orient = match from_side {
+                    Side::End => Orientation::Forward,
+                    Side::Start => Orientation::Reverse,
+                };
+                let from = handle(interner.get(from).expect("interned"), from_orient);
+
+                tos.keys().map(move |(to, to_side)| (from, to, *to_side))
+            })
+            .map(|(from, to, to_side)| {
+                let to_orient = match to_side {
+                    Side::Start => Orientation::Forward,
+                    Side::End => Orientation::Reverse,
+                };
+
+                (from, handle(interner.get(to).expect("interned"), to_orient))
+            })
+            .collect::<Vec<_>>();
+
+        let csr = CsrGraph::with_edges(interner.len() * 2, edges);
+
+        (csr, interner)
+    }
+
+    /// Number of handles, including the ones that are not nodes of the graph
+    pub fn handle_count(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn contains_node(&self, node: u32) -> bool {
+        self.nodes.binary_search(&node).is_ok()
+    }
+
+    /// Out-neighbors of a node, without allocating
+    pub fn neighbors_slice(&self, node: u32) -> &[u32] {
+        let node = node as usize;
+
+        if node >= self.handle_count() {
+            return &[];
+        }
+
+        &self.targets[self.offsets[node]..self.offsets[node + 1]]
+    }
+
+    pub fn has_edge(&self, from: u32, to: u32) -> bool {
+        self.neighbors_slice(from).binary_search(&to).is_ok()
+    }
+
+    fn grow(&mut self, node: u32) {
+        if self.offsets.is_empty() {
+            self.offsets.push(0);
+        }
+
+        let last = *self
+            .offsets
+            .last()
+            .expect("offsets has at least one element");
+
+        while self.handle_count() <= node as usize {
+            self.offsets.push(last);
+        }
+    }
+}
+
+impl Graph<u32> for CsrGraph {
+    fn new() -> Self {
+        CsrGraph {
+            offsets: vec![0],
+            targets: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    fn from_edges(edges: &[(u32, u32)]) -> Self {
+        let mut graph = CsrGraph::with_edges(0, edges.iter().copied());
+        graph.retain_edge_endpoints();
+
+        graph
+    }
+
+    fn to_adjecency_graph(&self) -> Cow<'_, AdjacencyGraph<u32>> {
+        let mut graph = AdjacencyGraph::new();
+
+        for node in self.nodes() {
+            graph.add_node(node);
+        }
+
+        for (from, to) in self.edges() {
+            graph.add_edge(from, to);
+        }
+
+        Cow::Owned(graph)
+    }
+
+    fn nodes(&self) -> BTreeSet<u32> {
+        self.nodes.iter().copied().collect()
+    }
+
+    fn adjacencies(&self) -> BTreeMap<u32, BTreeSet<u32>> {
+        (0..self.handle_count() as u32)
+            .filter(|node| !self.neighbors_slice(*node).is_empty())
+            .map(|node| (node, self.neighbors(&node)))
+            .collect()
+    }
+
+    fn edges(&self) -> BTreeSet<(u32, u32)> {
+        (0..self.handle_count() as u32)
+            .flat_map(|from| self.neighbors_slice(from).iter().map(move |to| (from, *to)))
+            .collect()
+    }
+
+    fn neighbors(&self, from: &u32) -> BTreeSet<u32> {
+        self.neighbors_slice(*from).iter().copied().collect()
+    }
+
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a u32>
+    where
+        u32: 'a,
+    {
+        self.nodes.iter()
+    }
+
+    fn neighbors_iter<'a>(&'a self, from: &u32) -> impl Iterator<Item = &'a u32>
+    where
+        u32: 'a,
+    {
+        self.neighbors_slice(*from).iter()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn out_edges_iter<'a>(&'a self, from: &u32) -> impl Iterator<Item = (&'a u32, &'a ())>
+    where
+        u32: 'a,
+        (): 'a,
+    {
+        self.neighbors_slice(*from).iter().map(|to| (to, &()))
+    }
+
+    fn edge<'a>(&'a self, from: &u32, to: &u32) -> Option<&'a ()>
+    where
+        u32: 'a,
+    {
+        self.has_edge(*from, *to).then_some(&())
+    }
+
+    fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    fn out_degree(&self, node: &u32) -> usize {
+        self.neighbors_slice(*node).len()
+    }
+
+    fn add_node(&mut self, node: u32) {
+        self.grow(node);
+
+        if let Err(i) = self.nodes.binary_search(&node) {
+            self.nodes.insert(i, node);
+        }
+    }
+
+    fn add_edge_with(&mut self, from: u32, to: u32, _: ()) {
+        self.add_node(from);
+        self.add_node(to);
+
+        let row = self.offsets[from as usize]..self.offsets[from as usize + 1];
+        if let Err(i) = self.targets[row.clone()].binary_search(&to) {
+            self.targets.insert(row.start + i, to);
+
+            for offset in self.offsets[from as usize + 1..].iter_mut() {
+                *offset += 1;
+            }
+        }
+    }
+
+    fn remove_node(&mut self, node: &u32) {
+        if !self.contains_node(*node) {
+            return;
+        }
+
+        let node = *node;
+        let edges = self
+            .edges()
+            .into_iter()
+            .filter(|(from, to)| *from != node && *to != node);
+
+        let mut graph = CsrGraph::with_edges(self.handle_count(), edges);
+        graph.nodes = std::mem::take(&mut self.nodes);
+        graph.nodes.retain(|other| *other != node);
+
+        *self = graph;
+    }
+
+    fn remove_edge(&mut self, from: &u32, to: &u32) {
+        if *from as usize >= self.handle_count() {
+            return;
+        }
+
+        let row = self.offsets[*from as usize]..self.offsets[*from as usize + 1];
+
+        if let Ok(i) = self.targets[row.clone()].binary_search(to) {
+            self.targets.remove(row.start + i);
+
+            for offset in self.offsets[*from as usize + 1..].iter_mut() {
+                *offset -= 1;
+            }
+        }
+    }
+
+    fn restricted(&self, nodes: &Vec<u32>) -> Self {
+        let index = nodes.iter().collect::<BTreeSet<_>>();
+
+        let edges = nodes.iter().flat_map(|from| {
+            self.neighbors_slice(*from)
+                .iter()
+                .filter(|to| index.contains(to))
+                .map(move |to| (*from, *to))
+        });
+
+        let mut graph = CsrGraph::with_edges(self.handle_count(), edges.collect::<Vec<_>>());
+        graph.retain_edge_endpoints();
+
+        graph
+    }
+}
@@ -1,20 +1,22 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
-    fmt::Debug,
+    fmt::{Debug, Display},
 };
 
-use super::{AdjacencyGraph, DirectedAcyclicGraph, Graph};
+use super::{AdjacencyGraph, CycleError, DirectedAcyclicGraph, Graph, PathLimits};
 
-impl<V> Graph<V> for DirectedAcyclicGraph<V>
+impl<V, E> Graph<V, E> for DirectedAcyclicGraph<V, E>
 where
     V: Ord + Clone,
+    E: Clone + Default,
 {
     fn new() -> Self {
         DirectedAcyclicGraph(AdjacencyGraph::new())
     }
 
-    fn to_adjecency_graph(&self) -> &AdjacencyGraph<V> {
-        &self.0
+    fn to_adjecency_graph(&self) -> Cow<'_, AdjacencyGraph<V, E>> {
+        Cow::Borrowed(&self.0)
     }
 
     fn nodes(&self) -> BTreeSet<V> {
@@ -33,12 +35,67 @@ where
         self.0.edges()
     }
 
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.0.nodes_iter()
+    }
+
+    fn neighbors_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.0.neighbors_iter(from)
+    }
+
+    fn out_edges_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = (&'a V, &'a E)>
+    where
+        V: 'a,
+        E: 'a,
+    {
+        self.0.out_edges_iter(from)
+    }
+
+    fn edge<'a>(&'a self, from: &V, to: &V) -> Option<&'a E>
+    where
+        V: 'a,
+    {
+        self.0.edge(from, to)
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = (&'a V, &'a V)>
+    where
+        V: 'a,
+    {
+        self.0.edges_iter()
+    }
+
+    fn node_count(&self) -> usize {
+        self.0.node_count()
+    }
+
+    fn out_degree(&self, node: &V) -> usize {
+        self.0.out_degree(node)
+    }
+
+    fn in_degree(&self, node: &V) -> usize {
+        self.0.in_degree(node)
+    }
+
+    fn in_degrees<'a>(&'a self) -> BTreeMap<&'a V, usize>
+    where
+        V: 'a,
+    {
+        self.0.in_degrees()
+    }
+
     fn add_node(&mut self, node: V) {
         self.0.add_node(node);
     }
 
-    fn add_edge(&mut self, from: V, to: V) {
-        self.0.add_edge(from, to);
+    fn add_edge_with(&mut self, from: V, to: V, payload: E) {
+        self.0.add_edge_with(from, to, payload);
     }
 
     fn remove_node(&mut self, node: &V) {
@@ -50,43 +107,237 @@ where
     }
 }
 
-impl<V> DirectedAcyclicGraph<V>
+impl<V, E> DirectedAcyclicGraph<V, E>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
-    pub fn all_paths<F>(&self, start: &V, mut visit_fn: F)
+    /// Adds an edge only if it doesn't close a cycle, otherwise returns the cycle it would
+    /// create. This searches for `from` starting from `to` so it is O(|V| + |E|) in the worst
+    /// case. Note that [Graph::add_edge] doesn't check for cycles.
+    pub fn try_add_edge(&mut self, from: V, to: V) -> Result<(), CycleError<V>> {
+        self.try_add_edge_with(from, to, E::default())
+    }
+
+    pub fn try_add_edge_with(&mut self, from: V, to: V, payload: E) -> Result<(), CycleError<V>> {
+        if let Some(path) = self.0.find_path(&to, &from) {
+            let mut cycle = vec![from.clone()];
+            cycle.extend(path.into_iter().take_while(|node| *node != from));
+
+            return Err(CycleError { cycle });
+        }
+
+        self.0.add_edge_with(from, to, payload);
+
+        Ok(())
+    }
+
+    /// See [AdjacencyGraph::index_predecessors]
+    pub fn index_predecessors(&mut self) {
+        self.0.index_predecessors();
+    }
+
+    /// Topological order with Kahn's algorithm, among the nodes with no incoming edges left the
+    /// smallest one comes first so the order is deterministic
+    pub fn topological_order(&self) -> Vec<V> {
+        let mut in_degrees = self.in_degrees();
+
+        let mut ready = in_degrees
+            .iter()
+            .filter(|(_, in_degree)| **in_degree == 0)
+            .map(|(node, _)| *node)
+            .collect::<BTreeSet<_>>();
+
+        let mut order = Vec::with_capacity(in_degrees.len());
+
+        while let Some(node) = ready.pop_first() {
+            order.push(node.clone());
+
+            for adj in self.neighbors_iter(node) {
+                let in_degree = in_degrees
+                    .get_mut(adj)
+                    .expect("every node has an in-degree");
+                *in_degree -= 1;
+
+                if *in_degree == 0 {
+                    ready.insert(adj);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Topological order as the reverse post-order of a DFS, visiting nodes and neighbors in
+    /// increasing order
+    pub fn topological_order_dfs(&self) -> Vec<V> {
+        let mut visited = BTreeSet::new();
+        let mut post_order = Vec::new();
+
+        for start in self.nodes_iter() {
+            if !visited.insert(start) {
+                continue;
+            }
+
+            let mut stack = vec![(start, self.neighbors_iter(start))];
+
+            while let Some((node, neighbors)) = stack.last_mut() {
+                let node = *node;
+
+                match neighbors.next() {
+                    Some(adj) => {
+                        if visited.insert(adj) {
+                            stack.push((adj, self.neighbors_iter(adj)));
+                        }
+                    }
+                    None => {
+                        post_order.push(node.clone());
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        post_order.reverse();
+        post_order
+    }
+
+    /// Visits every path from `start` to a sink, in lexicographic order, until `visit_fn` returns
+    /// false
+    pub fn all_paths<F>(&self, start: &V, visit_fn: F)
     where
         F: FnMut(Vec<V>) -> bool,
     {
-        let mut prev: BTreeMap<V, V> = BTreeMap::new();
+        self.visit_paths(start, None, PathLimits::default(), visit_fn);
+    }
 
-        let mut stack: Vec<(V, Option<V>)> = vec![(start.clone(), None)];
+    /// Paths from `from` to `to`, in lexicographic order
+    pub fn paths_between(&self, from: &V, to: &V, limits: PathLimits) -> Vec<Vec<V>> {
+        let mut paths = Vec::new();
 
-        while let Some((node, parent)) = stack.pop() {
-            if let Some(p) = parent {
-                prev.insert(node.clone(), p.clone());
-            }
+        self.visit_paths(from, Some(to), limits, |path| {
+            paths.push(path);
+            true
+        });
 
-            let neighbors = self.neighbors(&node);
-            if neighbors.is_empty() {
-                let mut path = vec![];
+        paths
+    }
 
-                let mut current = node.clone();
-                while let Some(next) = prev.get(&current) {
-                    path.push(current);
-                    current = next.clone();
+    /// Visits the paths from `start` to `end`, or to every sink if `end` is `None`, in
+    /// lexicographic order until `visit_fn` returns false or a limit is reached. The paths are
+    /// built on an explicit stack so the ones sharing a prefix are enumerated correctly, the
+    /// number of paths can still be exponential in the size of the graph.
+    pub fn visit_paths<F>(&self, start: &V, end: Option<&V>, limits: PathLimits, mut visit_fn: F)
+    where
+        F: FnMut(Vec<V>) -> bool,
+    {
+        // nodes that can reach the end, the other ones are never entered
+        let reaches_end = end.map(|end| {
+            let mut reaches_end = BTreeSet::new();
+
+            for node in self.topological_order().iter().rev() {
+                if node == end
+                    || self
+                        .neighbors_iter(node)
+                        .any(|adj| reaches_end.contains(adj))
+                {
+                    reaches_end.insert(node.clone());
                 }
+            }
+
+            reaches_end
+        });
 
-                path.reverse();
+        let is_end = |node: &V| match end {
+            Some(end) => node == end,
+            None => self.out_degree(node) == 0,
+        };
+        let can_continue = |node: &V| match &reaches_end {
+            Some(reaches_end) => reaches_end.contains(node),
+            None => true,
+        };
+        let within_length = |len: usize| limits.max_length.is_none_or(|max| len <= max);
+
+        let mut count = 0;
+        let mut visit = |path: &[&V]| {
+            count += 1;
+
+            visit_fn(path.iter().map(|node| (*node).clone()).collect())
+                && limits.max_count.is_none_or(|max| count < max)
+        };
+
+        if limits.max_count == Some(0) || !self.0.has_node(start) || !within_length(1) {
+            return;
+        }
+
+        if is_end(start) {
+            visit(&[start]);
+            return;
+        }
 
-                if !visit_fn(path) {
+        let mut path = vec![start];
+        let mut stack = vec![self.neighbors_iter(start)];
+
+        while let Some(neighbors) = stack.last_mut() {
+            let Some(adj) = neighbors.next() else {
+                stack.pop();
+                path.pop();
+                continue;
+            };
+
+            if !can_continue(adj) || !within_length(path.len() + 1) {
+                continue;
+            }
+
+            path.push(adj);
+
+            if is_end(adj) {
+                if !visit(&path) {
                     return;
                 }
-            }
 
-            for n in neighbors {
-                stack.push((n.clone(), Some(node.clone())));
+                path.pop();
+                continue;
             }
+
+            stack.push(self.neighbors_iter(adj));
         }
     }
 }
+
+impl<V, E> TryFrom<AdjacencyGraph<V, E>> for DirectedAcyclicGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    type Error = CycleError<V>;
+
+    /// Fails with one of the cycles of the graph if there is any, use [AdjacencyGraph::dag] to
+    /// drop the back edges instead
+    fn try_from(graph: AdjacencyGraph<V, E>) -> Result<Self, Self::Error> {
+        match graph.find_cycle() {
+            Some(cycle) => Err(CycleError { cycle }),
+            None => Ok(DirectedAcyclicGraph(graph)),
+        }
+    }
+}
+
+impl<V> Display for CycleError<V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "found cycle: ")?;
+
+        for node in self.cycle.iter() {
+            write!(f, "{:?} -> ", node)?;
+        }
+
+        match self.cycle.first() {
+            Some(first) => write!(f, "{:?}", first),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<V> std::error::Error for CycleError<V> where V: Debug {}
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet, VecDeque},
     fmt::Debug,
 };
@@ -7,9 +8,10 @@ use indicatif::ProgressIterator;
 
 use super::{AdjacencyGraph, Graph, UndirectedGraph};
 
-impl<V> Graph<V> for AdjacencyGraph<V>
+impl<V, E> Graph<V, E> for AdjacencyGraph<V, E>
 where
     V: Ord + Clone,
+    E: Clone + Default,
 {
     fn new() -> Self
     where
@@ -18,11 +20,12 @@ where
         AdjacencyGraph {
             nodes: BTreeSet::new(),
             adjacencies: BTreeMap::new(),
+            predecessors: None,
         }
     }
 
-    fn to_adjecency_graph(&self) -> &AdjacencyGraph<V> {
-        &self
+    fn to_adjecency_graph(&self) -> Cow<'_, AdjacencyGraph<V, E>> {
+        Cow::Borrowed(self)
     }
 
     fn nodes(&self) -> BTreeSet<V> {
@@ -30,44 +33,147 @@ where
     }
 
     fn adjacencies(&self) -> BTreeMap<V, BTreeSet<V>> {
-        self.adjacencies.clone()
+        self.adjacencies
+            .iter()
+            .map(|(from, tos)| (from.clone(), tos.keys().cloned().collect()))
+            .collect()
     }
 
     fn edges(&self) -> BTreeSet<(V, V)> {
-        self.adjacencies
-            .iter()
-            .flat_map(|(from, tos)| tos.iter().map(move |to| (from.clone(), to.clone())))
+        self.edges_iter()
+            .map(|(from, to)| (from.clone(), to.clone()))
             .collect()
     }
 
     fn neighbors(&self, from: &V) -> BTreeSet<V> {
-        if let Some(neighbors) = self.adjacencies.get(from) {
-            neighbors.clone()
-        } else {
-            BTreeSet::new()
+        self.neighbors_iter(from).cloned().collect()
+    }
+
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.nodes.iter()
+    }
+
+    fn neighbors_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.adjacencies
+            .get(from)
+            .into_iter()
+            .flat_map(|tos| tos.keys())
+    }
+
+    fn out_edges_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = (&'a V, &'a E)>
+    where
+        V: 'a,
+        E: 'a,
+    {
+        self.adjacencies.get(from).into_iter().flatten()
+    }
+
+    fn edge<'a>(&'a self, from: &V, to: &V) -> Option<&'a E>
+    where
+        V: 'a,
+    {
+        self.adjacencies.get(from)?.get(to)
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = (&'a V, &'a V)>
+    where
+        V: 'a,
+    {
+        self.adjacencies
+            .iter()
+            .flat_map(|(from, tos)| tos.keys().map(move |to| (from, to)))
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn out_degree(&self, node: &V) -> usize {
+        self.adjacencies.get(node).map_or(0, |tos| tos.len())
+    }
+
+    /// This is O(1) if the predecessors are indexed and O(|E|) otherwise
+    fn in_degree(&self, node: &V) -> usize {
+        match &self.predecessors {
+            Some(predecessors) => predecessors.get(node).map_or(0, |froms| froms.len()),
+            None => self.predecessors_iter(node).count(),
         }
     }
 
+    /// Reads the in-degrees from the predecessors if they are indexed
+    fn in_degrees<'a>(&'a self) -> BTreeMap<&'a V, usize>
+    where
+        V: 'a,
+    {
+        let Some(predecessors) = &self.predecessors else {
+            let mut in_degrees: BTreeMap<&V, usize> =
+                self.nodes.iter().map(|node| (node, 0)).collect();
+
+            for (_, to) in self.edges_iter() {
+                *in_degrees.entry(to).or_default() += 1;
+            }
+
+            return in_degrees;
+        };
+
+        self.nodes
+            .iter()
+            .map(|node| (node, predecessors.get(node).map_or(0, |froms| froms.len())))
+            .collect()
+    }
+
     fn add_node(&mut self, node: V) {
         self.nodes.insert(node);
     }
 
-    fn add_edge(&mut self, from: V, to: V) {
+    fn add_edge_with(&mut self, from: V, to: V, payload: E) {
         self.nodes.insert(from.clone());
         self.nodes.insert(to.clone());
 
+        if let Some(predecessors) = &mut self.predecessors {
+            predecessors
+                .entry(to.clone())
+                .or_default()
+                .insert(from.clone());
+        }
+
         self.adjacencies
             .entry(from)
-            .or_insert_with(BTreeSet::new)
-            .insert(to.clone());
+            .or_default()
+            .insert(to, payload);
     }
 
     fn remove_node(&mut self, node: &V) {
         self.nodes.remove(node);
-        self.adjacencies.remove(node);
+        let tos = self.adjacencies.remove(node).unwrap_or_default();
+
+        match &mut self.predecessors {
+            // O(deg) with the reverse adjacencies
+            Some(predecessors) => {
+                for from in predecessors.remove(node).unwrap_or_default() {
+                    if let Some(adjacencies) = self.adjacencies.get_mut(&from) {
+                        adjacencies.remove(node);
+                    }
+                }
 
-        for adjacencies in self.adjacencies.values_mut() {
-            adjacencies.remove(node);
+                for to in tos.keys() {
+                    if let Some(froms) = predecessors.get_mut(to) {
+                        froms.remove(node);
+                    }
+                }
+            }
+            // O(|V|) without
+            None => {
+                for adjacencies in self.adjacencies.values_mut() {
+                    adjacencies.remove(node);
+                }
+            }
         }
     }
 
@@ -75,39 +181,183 @@ where
         if let Some(adjacencies) = self.adjacencies.get_mut(from) {
             adjacencies.remove(to);
         }
+
+        if let Some(froms) = self
+            .predecessors
+            .as_mut()
+            .and_then(|predecessors| predecessors.get_mut(to))
+        {
+            froms.remove(from);
+        }
+    }
+}
+
+impl<V, E> AdjacencyGraph<V, E>
+where
+    V: Ord + Clone,
+    E: Clone + Default,
+{
+    /// Builds the reverse adjacencies of the graph and keeps them updated from now on, this
+    /// makes [AdjacencyGraph::predecessors] and [Graph::in_degree] O(deg) and
+    /// [Graph::remove_node] no longer scans all the adjacencies.
+    pub fn index_predecessors(&mut self) {
+        let mut predecessors: BTreeMap<V, BTreeSet<V>> = BTreeMap::new();
+
+        for (from, to) in self.edges_iter() {
+            predecessors
+                .entry(to.clone())
+                .or_default()
+                .insert(from.clone());
+        }
+
+        self.predecessors = Some(predecessors);
+    }
+
+    pub fn has_predecessor_index(&self) -> bool {
+        self.predecessors.is_some()
+    }
+
+    /// Nodes with an edge to the given node, this scans all the adjacencies if the
+    /// predecessors are not indexed
+    pub fn predecessors_iter<'a>(&'a self, node: &'a V) -> impl Iterator<Item = &'a V> + 'a {
+        let indexed = self
+            .predecessors
+            .as_ref()
+            .map(|predecessors| predecessors.get(node).into_iter().flatten());
+
+        let scanned = match indexed {
+            Some(_) => None,
+            None => Some(
+                self.adjacencies
+                    .iter()
+                    .filter(move |(_, tos)| tos.contains_key(node))
+                    .map(|(from, _)| from),
+            ),
+        };
+
+        indexed
+            .into_iter()
+            .flatten()
+            .chain(scanned.into_iter().flatten())
+    }
+
+    pub fn predecessors(&self, node: &V) -> BTreeSet<V> {
+        self.predecessors_iter(node).cloned().collect()
     }
 }
 
 #[allow(dead_code)]
-impl<V> AdjacencyGraph<V>
+impl<V, E> AdjacencyGraph<V, E>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
-    pub fn opposite(&self) -> AdjacencyGraph<V> {
+    pub fn opposite(&self) -> AdjacencyGraph<V, E> {
         let mut opposite = AdjacencyGraph::new();
 
         // O(|E|)
-        for (from, to) in self.edges() {
-            opposite.add_edge(to, from);
+        for (from, tos) in self.adjacencies.iter() {
+            for (to, payload) in tos {
+                opposite.add_edge_with(to.clone(), from.clone(), payload.clone());
+            }
         }
 
         opposite
     }
 
-    pub fn undirected(&self) -> UndirectedGraph<V> {
+    pub fn undirected(&self) -> UndirectedGraph<V, E> {
         let mut undirected = AdjacencyGraph::new();
 
         // O(|E|)
-        for (from, to) in self.edges() {
-            undirected.add_edge(from.clone(), to.clone());
-            undirected.add_edge(to.clone(), from.clone());
+        for (from, tos) in self.adjacencies.iter() {
+            for (to, payload) in tos {
+                undirected.add_edge_with(from.clone(), to.clone(), payload.clone());
+                undirected.add_edge_with(to.clone(), from.clone(), payload.clone());
+            }
         }
 
         UndirectedGraph(undirected)
     }
 
+    pub fn has_node(&self, node: &V) -> bool {
+        self.nodes.contains(node)
+    }
+
     pub fn has_edge(&self, from: &V, to: &V) -> bool {
-        self.neighbors(from).contains(to)
+        self.adjacencies
+            .get(from)
+            .is_some_and(|tos| tos.contains_key(to))
+    }
+
+    /// Shortest path from `from` to `to` (both included), a node always has the path to itself
+    pub fn find_path(&self, from: &V, to: &V) -> Option<Vec<V>> {
+        let mut prev: BTreeMap<&V, &V> = BTreeMap::new();
+        let mut visited = BTreeSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![node.clone()];
+
+                let mut current = node;
+                while let Some(p) = prev.get(current) {
+                    path.push((*p).clone());
+                    current = p;
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            for adj in self.neighbors_iter(node) {
+                if visited.insert(adj) {
+                    prev.insert(adj, node);
+                    queue.push_back(adj);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the nodes of a cycle in order if there is one, the last node has an edge back
+    /// to the first one
+    pub fn find_cycle(&self) -> Option<Vec<V>> {
+        let mut finished = BTreeSet::new();
+
+        for start in self.nodes.iter() {
+            if finished.contains(start) {
+                continue;
+            }
+
+            // the current path from start and the remaining neighbors of each node on it
+            let mut path = vec![start];
+            let mut on_path = BTreeSet::from([start]);
+            let mut continuations = vec![self.neighbors_iter(start)];
+
+            while let Some(neighbors) = continuations.last_mut() {
+                match neighbors.next() {
+                    Some(adj) if on_path.contains(adj) => {
+                        let i = path.iter().position(|node| *node == adj)?;
+                        return Some(path[i..].iter().map(|node| (*node).clone()).collect());
+                    }
+                    Some(adj) if !finished.contains(adj) => {
+                        path.push(adj);
+                        on_path.insert(adj);
+                        continuations.push(self.neighbors_iter(adj));
+                    }
+                    Some(_) => {}
+                    None => {
+                        let node = path.pop()?;
+                        on_path.remove(node);
+                        finished.insert(node);
+                        continuations.pop();
+                    }
+                }
+            }
+        }
+
+        None
     }
 
     pub fn shortest_path_matrix(&self) -> BTreeMap<V, BTreeMap<V, usize>> {
@@ -129,8 +379,8 @@ where
 
                 let distance = *distances.get(&node).unwrap();
 
-                for adj in self.neighbors(&node) {
-                    if !distances.contains_key(&adj) {
+                for adj in self.neighbors_iter(&node) {
+                    if !distances.contains_key(adj) {
                         distances.insert(adj.clone(), distance + 1);
                         queue.push_back(adj.clone());
                     }
@@ -147,7 +397,13 @@ where
         let mut visited = BTreeSet::new();
         let mut result = Vec::new();
 
-        let op = self.opposite();
+        // borrowed reverse adjacencies, only needed if the predecessors are not indexed
+        let mut reverse: BTreeMap<&V, Vec<&V>> = BTreeMap::new();
+        if self.predecessors.is_none() {
+            for (from, to) in self.edges_iter() {
+                reverse.entry(to).or_default().push(from);
+            }
+        }
 
         println!("Computing connected components...");
 
@@ -166,12 +422,19 @@ where
 
                 cc.insert(node.clone());
 
-                for adj in self.neighbors(&node) {
-                    stack.push(adj);
-                }
+                stack.extend(self.neighbors_iter(&node).cloned());
 
-                for adj in op.neighbors(&node) {
-                    stack.push(adj);
+                match &self.predecessors {
+                    Some(predecessors) => {
+                        stack.extend(predecessors.get(&node).into_iter().flatten().cloned())
+                    }
+                    None => stack.extend(
+                        reverse
+                            .get(&node)
+                            .into_iter()
+                            .flatten()
+                            .map(|from| (*from).clone()),
+                    ),
                 }
             }
 
@@ -186,7 +449,7 @@ where
         let mut to_remove = Vec::new();
 
         for node in self.nodes.iter() {
-            if self.neighbors(node).is_empty() {
+            if self.out_degree(node) == 0 {
                 to_remove.push(node.clone());
             }
         }
@@ -10,6 +10,7 @@ use indicatif::ProgressBar;
 
 use super::{AdjacencyGraph, DirectedAcyclicGraph, Graph};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum EdgeType {
     TreeEdge,
@@ -35,8 +36,14 @@ impl<V> ClassifyState<V>
 where
     V: Ord + Eq + Clone + Debug,
 {
-    pub fn classify_edges_rec(mut self, graph: &AdjacencyGraph<V>) -> BTreeMap<(V, V), EdgeType> {
-        for start in graph.nodes().iter() {
+    pub fn classify_edges_rec<E>(
+        mut self,
+        graph: &AdjacencyGraph<V, E>,
+    ) -> BTreeMap<(V, V), EdgeType>
+    where
+        E: Clone + Default,
+    {
+        for start in graph.nodes_iter() {
             if self.visited.contains(start) {
                 continue;
             }
@@ -45,10 +52,13 @@ where
         }
 
         self.progress_bar.finish();
-        return self.edge_types;
+        self.edge_types
     }
 
-    pub fn dfs(&mut self, graph: &AdjacencyGraph<V>, node: &V, parent: Option<&V>) {
+    pub fn dfs<E>(&mut self, graph: &AdjacencyGraph<V, E>, node: &V, parent: Option<&V>)
+    where
+        E: Clone + Default,
+    {
         if self.visited.contains(node) {
             return;
         }
@@ -85,13 +95,14 @@ where
     }
 }
 
-impl<V> AdjacencyGraph<V>
+impl<V, E> AdjacencyGraph<V, E>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
     pub fn compute_edge_types_rec(&self) -> BTreeMap<(V, V), EdgeType> {
-        return ClassifyState {
-            progress_bar: ProgressBar::new(self.nodes().len() as u64),
+        ClassifyState {
+            progress_bar: ProgressBar::new(self.node_count() as u64),
 
             edge_types: BTreeMap::new(),
             visited: BTreeSet::new(),
@@ -99,7 +110,7 @@ where
             finished_nodes: BTreeSet::new(),
             time: 0,
         }
-        .classify_edges_rec(self);
+        .classify_edges_rec(self)
     }
 
     pub fn compute_edge_types(&self) -> BTreeMap<(V, V), EdgeType> {
@@ -109,7 +120,7 @@ where
         let mut finished_nodes = BTreeSet::new();
         let mut time = 0;
 
-        let progress_bar = ProgressBar::new(self.nodes().len() as u64);
+        let progress_bar = ProgressBar::new(self.node_count() as u64);
 
         enum Continuation<V> {
             Start { node: V, parent: Option<V> },
@@ -117,7 +128,7 @@ where
             End { node: V },
         }
 
-        for start in self.nodes().iter() {
+        for start in self.nodes_iter() {
             if visited.contains(start) {
                 continue;
             }
@@ -150,7 +161,7 @@ where
                         node,
                         continue_from: index,
                     } => {
-                        for (i, adj) in self.neighbors(&node).iter().enumerate() {
+                        for (i, adj) in self.neighbors_iter(&node).enumerate() {
                             if i < index {
                                 continue;
                             }
@@ -188,11 +199,12 @@ where
         }
 
         progress_bar.finish();
-        return edge_types;
+        edge_types
     }
 
-    // Constructs a Directed Acyclic Graph from the current graph by stripping out the back edges
-    pub fn dag(&self) -> DirectedAcyclicGraph<V> {
+    // Constructs a Directed Acyclic Graph from the current graph by stripping out the back edges,
+    // the other edges keep their payloads
+    pub fn dag(&self) -> DirectedAcyclicGraph<V, E> {
         let edge_types = self.compute_edge_types();
 
         let mut graph = AdjacencyGraph::new();
@@ -201,11 +213,12 @@ where
             match edge_type {
                 EdgeType::BackEdge => {}
                 _ => {
-                    graph.add_edge(from.clone(), to.clone());
+                    let payload = self.edge(from, to).cloned().unwrap_or_default();
+                    graph.add_edge_with(from.clone(), to.clone(), payload);
                 }
             }
         }
 
-        return DirectedAcyclicGraph(graph);
+        DirectedAcyclicGraph(graph)
     }
 }
@@ -0,0 +1,402 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use super::{edge_types::EdgeType, AdjacencyGraph, DirectedAcyclicGraph, Graph};
+
+/// Heuristics to pick the edges to remove to make a graph acyclic, finding the smallest such set
+/// (the minimum feedback arc set) is NP-hard.
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum FeedbackArcSetStrategy {
+    /// Removes the back edges of a DFS visiting the nodes in order, this is what
+    /// [AdjacencyGraph::dag] does
+    Dfs,
+    /// Orders the nodes with the greedy heuristic of Eades, Lin and Smyth, repeatedly taking
+    /// sinks, sources or the node with the largest out-degree minus in-degree, and removes the
+    /// edges going backwards in the order
+    EadesLinSmyth,
+    /// Like [FeedbackArcSetStrategy::EadesLinSmyth] but applied to each strongly connected
+    /// component on its own, edges between components are never on a cycle so they are kept
+    SccLocal,
+    /// Adds the edges traversed by the given paths first and then all the other ones, skipping
+    /// the edges that would close a cycle
+    PathGuided,
+}
+
+impl FeedbackArcSetStrategy {
+    pub const ALL: [FeedbackArcSetStrategy; 4] = [
+        FeedbackArcSetStrategy::Dfs,
+        FeedbackArcSetStrategy::EadesLinSmyth,
+        FeedbackArcSetStrategy::SccLocal,
+        FeedbackArcSetStrategy::PathGuided,
+    ];
+}
+
+impl Display for FeedbackArcSetStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeedbackArcSetStrategy::Dfs => write!(f, "dfs"),
+            FeedbackArcSetStrategy::EadesLinSmyth => write!(f, "els"),
+            FeedbackArcSetStrategy::SccLocal => write!(f, "scc"),
+            FeedbackArcSetStrategy::PathGuided => write!(f, "paths"),
+        }
+    }
+}
+
+impl FromStr for FeedbackArcSetStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FeedbackArcSetStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "invalid strategy: {:?}, expected one of dfs, els, scc, paths",
+                    s
+                )
+            })
+    }
+}
+
+impl<V, E> AdjacencyGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Edges to remove to make the graph acyclic, `paths` are only used by
+    /// [FeedbackArcSetStrategy::PathGuided]
+    pub fn feedback_arc_set(
+        &self,
+        strategy: FeedbackArcSetStrategy,
+        paths: &[Vec<V>],
+    ) -> BTreeSet<(V, V)> {
+        match strategy {
+            FeedbackArcSetStrategy::Dfs => self
+                .compute_edge_types()
+                .into_iter()
+                .filter(|(_, edge_type)| *edge_type == EdgeType::BackEdge)
+                .map(|(edge, _)| edge)
+                .collect(),
+            FeedbackArcSetStrategy::EadesLinSmyth => {
+                let nodes = self.nodes.iter().collect::<BTreeSet<_>>();
+                let order = self.eades_lin_smyth_order(&nodes);
+
+                self.backward_edges(&nodes, &order)
+            }
+            FeedbackArcSetStrategy::SccLocal => {
+                let mut removed = BTreeSet::new();
+
+                for component in self.tarjan_components() {
+                    let nodes = component.into_iter().collect::<BTreeSet<_>>();
+                    let order = self.eades_lin_smyth_order(&nodes);
+
+                    removed.extend(self.backward_edges(&nodes, &order));
+                }
+
+                removed
+            }
+            FeedbackArcSetStrategy::PathGuided => self.path_guided_feedback_arc_set(paths),
+        }
+    }
+
+    /// Copy of the graph without the given edges, they should be a feedback arc set
+    pub fn dag_without(&self, removed: &BTreeSet<(V, V)>) -> DirectedAcyclicGraph<V, E> {
+        let mut graph = AdjacencyGraph::new();
+
+        for node in self.nodes.iter() {
+            graph.add_node(node.clone());
+        }
+
+        for (from, tos) in self.adjacencies.iter() {
+            for (to, payload) in tos {
+                if !removed.contains(&(from.clone(), to.clone())) {
+                    graph.add_edge_with(from.clone(), to.clone(), payload.clone());
+                }
+            }
+        }
+
+        DirectedAcyclicGraph(graph)
+    }
+
+    /// Edges inside `nodes` that go backwards (or are self loops) in the given order
+    fn backward_edges(&self, nodes: &BTreeSet<&V>, order: &[&V]) -> BTreeSet<(V, V)> {
+        let positions = order
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (*node, i))
+            .collect::<BTreeMap<_, _>>();
+
+        nodes
+            .iter()
+            .flat_map(|from| self.neighbors_iter(from).map(move |to| (*from, to)))
+            .filter(
+                |(from, to)| match (positions.get(from), positions.get(to)) {
+                    (Some(i), Some(j)) => i >= j,
+                    _ => false,
+                },
+            )
+            .map(|(from, to)| (from.clone(), to.clone()))
+            .collect()
+    }
+
+    /// Orders the given nodes with the Eades–Lin–Smyth heuristic looking only at the edges
+    /// between them, O(|E| log |V|)
+    fn eades_lin_smyth_order<'a>(&'a self, nodes: &BTreeSet<&'a V>) -> Vec<&'a V> {
+        let mut successors: BTreeMap<&V, Vec<&V>> = BTreeMap::new();
+        let mut predecessors: BTreeMap<&V, Vec<&V>> = BTreeMap::new();
+
+        for from in nodes.iter() {
+            for to in self.neighbors_iter(from) {
+                // self loops are always removed and don't change the order
+                if *from != to && nodes.contains(to) {
+                    successors.entry(from).or_default().push(to);
+                    predecessors.entry(to).or_default().push(from);
+                }
+            }
+        }
+
+        let mut in_degrees = BTreeMap::new();
+        let mut out_degrees = BTreeMap::new();
+        let mut deltas = BTreeSet::new();
+        let mut sources = Vec::new();
+        let mut sinks = Vec::new();
+
+        for node in nodes.iter() {
+            let in_degree = predecessors.get(node).map_or(0, |froms| froms.len());
+            let out_degree = successors.get(node).map_or(0, |tos| tos.len());
+
+            in_degrees.insert(*node, in_degree);
+            out_degrees.insert(*node, out_degree);
+            deltas.insert((out_degree as i64 - in_degree as i64, *node));
+
+            if out_degree == 0 {
+                sinks.push(*node);
+            } else if in_degree == 0 {
+                sources.push(*node);
+            }
+        }
+
+        let mut removed = BTreeSet::new();
+        let mut head = Vec::new();
+        let mut tail = Vec::new();
+
+        while removed.len() < nodes.len() {
+            let (node, is_sink) = if let Some(sink) = sinks.pop() {
+                (sink, true)
+            } else if let Some(source) = sources.pop() {
+                (source, false)
+            } else {
+                let (_, node) = *deltas.last().expect("some node is left");
+                (node, false)
+            };
+
+            // nodes can be queued both as sinks and sources
+            if !removed.insert(node) {
+                continue;
+            }
+
+            if is_sink {
+                tail.push(node);
+            } else {
+                head.push(node);
+            }
+
+            deltas.remove(&(out_degrees[node] as i64 - in_degrees[node] as i64, node));
+
+            for to in successors.get(node).into_iter().flatten() {
+                if removed.contains(to) {
+                    continue;
+                }
+
+                let (in_degree, out_degree) = (in_degrees[to], out_degrees[to]);
+                deltas.remove(&(out_degree as i64 - in_degree as i64, *to));
+                deltas.insert((out_degree as i64 - in_degree as i64 + 1, *to));
+                in_degrees.insert(*to, in_degree - 1);
+
+                if in_degree == 1 {
+                    sources.push(*to);
+                }
+            }
+
+            for from in predecessors.get(node).into_iter().flatten() {
+                if removed.contains(from) {
+                    continue;
+                }
+
+                let (in_degree, out_degree) = (in_degrees[from], out_degrees[from]);
+                deltas.remove(&(out_degree as i64 - in_degree as i64, *from));
+                deltas.insert((out_degree as i64 - in_degree as i64 - 1, *from));
+                out_degrees.insert(*from, out_degree - 1);
+
+                if out_degree == 1 {
+                    sinks.push(*from);
+                }
+            }
+        }
+
+        tail.reverse();
+        head.extend(tail);
+        head
+    }
+
+    /// Adds the edges of each component to an empty DAG, the ones traversed by the paths first,
+    /// removing the edges that would close a cycle. The topological order of the DAG is kept
+    /// with [IncrementalOrder], so an edge only looks at the nodes between its endpoints in the
+    /// current order instead of searching the whole component.
+    fn path_guided_feedback_arc_set(&self, paths: &[Vec<V>]) -> BTreeSet<(V, V)> {
+        let mut removed = BTreeSet::new();
+
+        // edges between components can't close a cycle, so each component is made acyclic on
+        // its own to keep the cycle checks small
+        for component in self.tarjan_components() {
+            if component.len() == 1 {
+                let node = component[0];
+                if self.has_edge(node, node) {
+                    removed.insert((node.clone(), node.clone()));
+                }
+
+                continue;
+            }
+
+            let indices = component
+                .iter()
+                .enumerate()
+                .map(|(i, node)| (*node, i))
+                .collect::<BTreeMap<_, _>>();
+            let mut order = IncrementalOrder::new(component.len());
+
+            let path_edges = paths
+                .iter()
+                .flat_map(|path| path.windows(2))
+                .filter(|step| indices.contains_key(&step[0]) && indices.contains_key(&step[1]))
+                .filter(|step| self.has_edge(&step[0], &step[1]))
+                .map(|step| (&step[0], &step[1]));
+
+            let other_edges = component
+                .iter()
+                .flat_map(|from| self.neighbors_iter(from).map(move |to| (*from, to)))
+                .filter(|(_, to)| indices.contains_key(to));
+
+            for (from, to) in path_edges.chain(other_edges) {
+                if !order.try_add_edge(indices[from], indices[to]) {
+                    removed.insert((from.clone(), to.clone()));
+                }
+            }
+        }
+
+        removed
+    }
+}
+
+/// Topological order of a DAG on the nodes `0..n` that grows one edge at a time, with the
+/// algorithm of Pearce and Kelly (2006). Adding an edge that goes backwards in the current order
+/// only visits and reorders the nodes between its endpoints, edges going forward are O(1).
+struct IncrementalOrder {
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    /// position of every node in the order
+    positions: Vec<usize>,
+    visited: Vec<bool>,
+}
+
+impl IncrementalOrder {
+    fn new(node_count: usize) -> Self {
+        IncrementalOrder {
+            successors: vec![Vec::new(); node_count],
+            predecessors: vec![Vec::new(); node_count],
+            positions: (0..node_count).collect(),
+            visited: vec![false; node_count],
+        }
+    }
+
+    /// Adds the edge if it doesn't close a cycle, returns whether the edge is in the DAG
+    fn try_add_edge(&mut self, from: usize, to: usize) -> bool {
+        if self.successors[from].contains(&to) {
+            return true;
+        }
+
+        let (lower, upper) = (self.positions[to], self.positions[from]);
+
+        if lower < upper {
+            // nodes reachable from `to` that come before `from`, reaching `from` closes a cycle
+            let Some(forward) = self.visit(to, upper, true) else {
+                return false;
+            };
+            // nodes reaching `from` that come after `to`, they can't overlap with `forward`
+            let backward = self
+                .visit(from, lower, false)
+                .expect("the edge closes no cycle");
+
+            self.reorder(backward, forward);
+        } else if lower == upper {
+            return false;
+        }
+
+        self.successors[from].push(to);
+        self.predecessors[to].push(from);
+
+        true
+    }
+
+    /// Nodes reached from `start` along the successors (or the predecessors if not `forward`)
+    /// without going past `bound` in the order, `None` if a node at `bound` is reached
+    fn visit(&mut self, start: usize, bound: usize, forward: bool) -> Option<Vec<usize>> {
+        let mut reached = vec![start];
+        let mut stack = vec![start];
+        let mut cycle = false;
+        self.visited[start] = true;
+
+        'visit: while let Some(node) = stack.pop() {
+            let adjacent = match forward {
+                true => &self.successors[node],
+                false => &self.predecessors[node],
+            };
+
+            for &adj in adjacent {
+                let position = self.positions[adj];
+
+                if position == bound {
+                    cycle = true;
+                    break 'visit;
+                }
+
+                let within = match forward {
+                    true => position < bound,
+                    false => position > bound,
+                };
+
+                if within && !self.visited[adj] {
+                    self.visited[adj] = true;
+                    reached.push(adj);
+                    stack.push(adj);
+                }
+            }
+        }
+
+        for node in reached.iter() {
+            self.visited[*node] = false;
+        }
+
+        (!cycle).then_some(reached)
+    }
+
+    /// Moves the nodes of `backward` before the ones of `forward`, reusing their positions
+    fn reorder(&mut self, mut backward: Vec<usize>, mut forward: Vec<usize>) {
+        backward.sort_by_key(|node| self.positions[*node]);
+        forward.sort_by_key(|node| self.positions[*node]);
+
+        let mut positions = backward
+            .iter()
+            .chain(forward.iter())
+            .map(|node| self.positions[*node])
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+
+        for (node, position) in backward.into_iter().chain(forward).zip(positions) {
+            self.positions[node] = position;
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use crate::rolling_hash::RollingHasher;
+
+use super::{DirectedAcyclicGraph, Graph};
+
+/// The hashes of [RollingHasher] wrap around 2^64 before being reduced, so the modulus must be a
+/// power of two to compare them correctly, and the base must be odd to be invertible
+const HASH_MODULUS: u64 = 1 << 32;
+const HASH_BASE: u64 = 257;
+
+/// Occurrence of a pattern in the graph, it starts at `offset` in the sequence of `start` and
+/// continues along `path`, that starts with `start` and ends with the node of its last base
+#[derive(Debug, Clone, PartialEq)]
+pub struct KmerOccurrence<V> {
+    pub start: V,
+    pub offset: usize,
+    pub path: Vec<V>,
+}
+
+impl<V, E> DirectedAcyclicGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Finds every occurrence of `pattern` along any path of the graph, without enumerating the
+    /// paths. `sequence` gives the sequence of a node and `overlap` the length of the overlap of
+    /// an edge, that is skipped when walking it. Windows inside a node are hashed rolling over
+    /// its sequence, windows crossing the end of a node keep rolling over the successors, with
+    /// a copy of the hasher for every branch, until they no longer start in that node. Windows
+    /// starting inside the overlap with a predecessor are skipped, as they are already found
+    /// starting in the predecessor.
+    ///
+    /// Returns the occurrences sorted by start node, offset and path.
+    pub fn find_pattern<F, G>(
+        &self,
+        pattern: &[u8],
+        sequence: F,
+        overlap: G,
+    ) -> Vec<KmerOccurrence<V>>
+    where
+        F: Fn(&V) -> Vec<u8>,
+        G: Fn(&E) -> usize,
+    {
+        let mut occurrences = Vec::new();
+        let k = pattern.len();
+
+        if k == 0 {
+            return occurrences;
+        }
+
+        let sequences = self
+            .nodes_iter()
+            .map(|node| (node, sequence(node)))
+            .collect::<BTreeMap<_, _>>();
+
+        // the bases at the start of a node shared with all its predecessors, when the overlaps of
+        // the incoming edges differ only the shortest one is skipped
+        let mut incoming_overlaps: BTreeMap<&V, usize> = BTreeMap::new();
+        for from in self.nodes_iter() {
+            for (to, payload) in self.out_edges_iter(from) {
+                let overlap = overlap(payload);
+
+                incoming_overlaps
+                    .entry(to)
+                    .and_modify(|skipped| *skipped = (*skipped).min(overlap))
+                    .or_insert(overlap);
+            }
+        }
+
+        let mut pattern_hasher = RollingHasher::new(HASH_MODULUS, HASH_BASE);
+        for value in pattern {
+            pattern_hasher.add_last(*value);
+        }
+        let pattern_hash = pattern_hasher.hash();
+
+        // the hashes can collide, so every match is checked against the pattern
+        let matches = |hasher: &RollingHasher<u8>| {
+            hasher.compare(&hasher.hash(), &pattern_hash) && hasher.word().iter().eq(pattern)
+        };
+
+        for (start, start_sequence) in sequences.iter() {
+            let len = start_sequence.len();
+            let first_start = incoming_overlaps.get(start).copied().unwrap_or(0);
+
+            // windows inside the node
+            let mut hasher = RollingHasher::new(HASH_MODULUS, HASH_BASE);
+            for (i, value) in start_sequence.iter().enumerate() {
+                if i < k {
+                    hasher.add_last(*value);
+                } else {
+                    hasher.advance(*value);
+                }
+
+                if i + 1 >= k + first_start && matches(&hasher) {
+                    occurrences.push(KmerOccurrence {
+                        start: (*start).clone(),
+                        offset: i + 1 - k,
+                        path: vec![(*start).clone()],
+                    });
+                }
+            }
+
+            if first_start >= len {
+                continue;
+            }
+
+            // windows crossing the end of the node start from its last k - 1 bases
+            let first_offset = len.saturating_sub(k - 1).max(first_start);
+            let mut hasher = RollingHasher::new(HASH_MODULUS, HASH_BASE);
+            for value in start_sequence[first_offset..].iter() {
+                hasher.add_last(*value);
+            }
+
+            let mut stack = self
+                .neighbors_iter(start)
+                .map(|adj| (*start, adj, hasher.clone(), vec![*start, adj], first_offset))
+                .collect::<Vec<_>>();
+            stack.reverse();
+
+            while let Some((prev, node, mut hasher, path, mut offset)) = stack.pop() {
+                let skipped = self.edge(prev, node).map_or(0, &overlap);
+                let node_sequence = &sequences[node];
+
+                for value in node_sequence.iter().skip(skipped) {
+                    if hasher.word().len() == k {
+                        hasher.remove_first();
+                        offset += 1;
+                    }
+
+                    if offset >= len {
+                        break;
+                    }
+
+                    hasher.add_last(*value);
+
+                    if hasher.word().len() == k && matches(&hasher) {
+                        occurrences.push(KmerOccurrence {
+                            start: (*start).clone(),
+                            offset,
+                            path: path.iter().map(|node| (*node).clone()).collect(),
+                        });
+                    }
+                }
+
+                if offset >= len || (hasher.word().len() == k && offset + 1 >= len) {
+                    continue;
+                }
+
+                let mut next = self
+                    .neighbors_iter(node)
+                    .map(|adj| {
+                        let mut path = path.clone();
+                        path.push(adj);
+
+                        (node, adj, hasher.clone(), path, offset)
+                    })
+                    .collect::<Vec<_>>();
+                next.reverse();
+                stack.extend(next);
+            }
+        }
+
+        occurrences
+            .sort_by(|a, b| (&a.start, a.offset, &a.path).cmp(&(&b.start, b.offset, &b.path)));
+
+        occurrences
+    }
+}
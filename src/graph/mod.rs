@@ -1,11 +1,16 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     fmt::Debug,
 };
 
-pub trait Graph<V>
+/// Directed graph on nodes of type `V`, every edge carries a payload of type `E` (for example
+/// the overlap of a link, a coverage count or a weight). Edges added without a payload get
+/// `E::default()`.
+pub trait Graph<V, E = ()>
 where
     V: Ord + Clone,
+    E: Clone + Default,
 {
     fn new() -> Self
     where
@@ -24,15 +29,89 @@ where
         graph
     }
 
-    fn to_adjecency_graph(&self) -> &AdjacencyGraph<V>;
+    fn to_adjecency_graph(&self) -> Cow<'_, AdjacencyGraph<V, E>>;
 
     fn nodes(&self) -> BTreeSet<V>;
     fn adjacencies(&self) -> BTreeMap<V, BTreeSet<V>>;
     fn edges(&self) -> BTreeSet<(V, V)>;
     fn neighbors(&self, from: &V) -> BTreeSet<V>;
 
+    // Borrowing versions of the methods above, these don't clone the nodes so prefer them on big
+    // graphs
+
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a;
+
+    fn neighbors_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a;
+
+    /// Out-neighbors of a node together with the payload of the edge to them
+    fn out_edges_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = (&'a V, &'a E)>
+    where
+        V: 'a,
+        E: 'a;
+
+    fn edge<'a>(&'a self, from: &V, to: &V) -> Option<&'a E>
+    where
+        V: 'a,
+    {
+        self.out_edges_iter(from)
+            .find(|(adj, _)| *adj == to)
+            .map(|(_, payload)| payload)
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = (&'a V, &'a V)>
+    where
+        V: 'a,
+    {
+        self.nodes_iter()
+            .flat_map(move |from| self.neighbors_iter(from).map(move |to| (from, to)))
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes_iter().count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edges_iter().count()
+    }
+
+    fn out_degree(&self, node: &V) -> usize {
+        self.neighbors_iter(node).count()
+    }
+
+    /// This is O(|E|) by default as it has to look at all the edges
+    fn in_degree(&self, node: &V) -> usize {
+        self.edges_iter().filter(|(_, to)| *to == node).count()
+    }
+
+    /// In-degrees of all the nodes, with one pass over the edges by default
+    fn in_degrees<'a>(&'a self) -> BTreeMap<&'a V, usize>
+    where
+        V: 'a,
+    {
+        let mut in_degrees: BTreeMap<&V, usize> = self.nodes_iter().map(|node| (node, 0)).collect();
+
+        for (_, to) in self.edges_iter() {
+            *in_degrees.entry(to).or_default() += 1;
+        }
+
+        in_degrees
+    }
+
     fn add_node(&mut self, node: V);
-    fn add_edge(&mut self, from: V, to: V);
+
+    /// Adds an edge with the default payload, the payload of an already present edge is kept
+    fn add_edge(&mut self, from: V, to: V) {
+        if self.edge(&from, &to).is_none() {
+            self.add_edge_with(from, to, E::default());
+        }
+    }
+
+    /// Adds an edge with the given payload, replacing the payload of an already present edge
+    fn add_edge_with(&mut self, from: V, to: V, payload: E);
 
     fn remove_node(&mut self, node: &V);
     fn remove_edge(&mut self, from: &V, to: &V);
@@ -45,9 +124,9 @@ where
         let mut restricted = Self::new();
 
         for node in nodes {
-            for adj in self.neighbors(&node) {
-                if nodes_index.contains(&adj) {
-                    restricted.add_edge(node.clone(), adj.clone());
+            for (adj, payload) in self.out_edges_iter(node) {
+                if nodes_index.contains(adj) {
+                    restricted.add_edge_with(node.clone(), adj.clone(), payload.clone());
                 }
             }
         }
@@ -57,28 +136,86 @@ where
 }
 
 #[derive(Debug, Clone)]
-pub struct AdjacencyGraph<V>
+pub struct AdjacencyGraph<V, E = ()>
 where
     V: Clone,
 {
     nodes: BTreeSet<V>,
-    adjacencies: BTreeMap<V, BTreeSet<V>>,
+    adjacencies: BTreeMap<V, BTreeMap<V, E>>,
+    /// Optional reverse adjacencies, see [AdjacencyGraph::index_predecessors]
+    predecessors: Option<BTreeMap<V, BTreeSet<V>>>,
 }
 
 #[derive(Debug, Clone)]
-pub struct UndirectedGraph<V>(AdjacencyGraph<V>)
+pub struct UndirectedGraph<V, E = ()>(AdjacencyGraph<V, E>)
 where
     V: Clone;
 
 #[derive(Debug, Clone)]
-pub struct DirectedAcyclicGraph<V>(AdjacencyGraph<V>)
+pub struct DirectedAcyclicGraph<V, E = ()>(AdjacencyGraph<V, E>)
 where
     V: Clone;
 
+/// Returned when an edge would close a cycle in a [DirectedAcyclicGraph]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleError<V> {
+    /// Nodes of the cycle in order, the last one has an edge back to the first one
+    pub cycle: Vec<V>,
+}
+
+/// Limits on the paths enumerated by [DirectedAcyclicGraph::visit_paths], `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PathLimits {
+    /// Maximum number of paths to visit
+    pub max_count: Option<usize>,
+    /// Maximum number of nodes of a path, longer paths are not visited
+    pub max_length: Option<usize>,
+}
+
+/// One of the two ends of a segment, a segment traversed forward is entered from its `Start` and
+/// left from its `End`
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Side {
+    Start,
+    End,
+}
+
+/// Variation graph where each link connects two segment sides, so a single edge is enough to
+/// walk it on both strands
+#[derive(Debug, Clone)]
+pub struct BidirectedGraph<V, E = ()>
+where
+    V: Clone,
+{
+    nodes: BTreeSet<V>,
+    /// symmetric, every edge is stored from both of its sides with the same payload
+    adjacencies: BTreeMap<(V, Side), BTreeMap<(V, Side), E>>,
+}
+
+/// Compressed sparse row graph on `u32` node handles, the out-neighbors of node `i` are
+/// `targets[offsets[i]..offsets[i + 1]]` (sorted). Meant to be built in bulk, single edge
+/// insertions and removals are O(|E|).
+#[derive(Debug, Clone, Default)]
+pub struct CsrGraph {
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+    /// sorted handles that are nodes of the graph, handles can be unused or removed
+    nodes: Vec<u32>,
+}
+
+pub mod bidirected;
+pub mod bubbles;
+pub mod csr;
 pub mod dag;
 pub mod directed;
 pub mod edge_types;
+pub mod feedback_arc_set;
+pub mod kmer_search;
+pub mod path_sampling;
+pub mod scc;
+pub mod sort;
 pub mod undirected;
+pub mod unitigs;
 
 #[cfg(test)]
 mod tests {
@@ -90,7 +227,7 @@ mod tests {
     where
         T: Debug,
     {
-        println!("");
+        println!();
         println!("Edge types:");
 
         for (edge, edge_type) in edge_types {
@@ -114,7 +251,7 @@ mod tests {
 
     #[test]
     fn test_compute_edge_types_cycle() {
-        let g = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (2, 3), (3, 0)]);
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (2, 3), (3, 0)]);
 
         let edge_types = g.compute_edge_types();
         print_edge_types(&edge_types);
@@ -128,7 +265,7 @@ mod tests {
 
     #[test]
     fn test_compute_edge_types_forward() {
-        let g = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (0, 2)]);
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (0, 2)]);
 
         let edge_types = g.compute_edge_types();
         print_edge_types(&edge_types);
@@ -141,7 +278,8 @@ mod tests {
 
     #[test]
     fn test_compute_edge_types_cross() {
-        let g = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (0, 3), (3, 4), (2, 4)]);
+        let g: AdjacencyGraph<_> =
+            AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (0, 3), (3, 4), (2, 4)]);
 
         let edge_types = g.compute_edge_types();
         print_edge_types(&edge_types);
@@ -156,7 +294,7 @@ mod tests {
 
     #[test]
     fn test_compute_edge_types_all() {
-        let g = AdjacencyGraph::from_edges(&[
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[
             //
             ("u", "v"),
             ("u", "x"),
@@ -182,7 +320,7 @@ mod tests {
 
     #[test]
     fn test_compact_chains() {
-        let mut g = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (2, 3), (3, 4)]).undirected();
+        let mut g = AdjacencyGraph::<_>::from_edges(&[(0, 1), (1, 2), (2, 3), (3, 4)]).undirected();
 
         println!("Compacting chains...");
         println!("{:?}", g);
@@ -192,7 +330,7 @@ mod tests {
 
     #[test]
     fn test_all_paths() {
-        let g = AdjacencyGraph::from_edges(&[
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[
             //
             ("u", "v"),
             ("u", "x"),
@@ -209,9 +347,779 @@ mod tests {
 
         let g = g.dag();
 
+        // "x" and "i" have more than one parent, every path still starts from "u"
+        let mut paths = vec![];
+        g.all_paths(&"u", |path| {
+            paths.push(path);
+            true
+        });
+
+        assert_eq!(
+            paths,
+            vec![
+                vec!["u", "v", "y", "w", "z", "x", "g", "i"],
+                vec!["u", "v", "y", "w", "z", "x", "h", "i"],
+                vec!["u", "v", "y", "x", "g", "i"],
+                vec!["u", "v", "y", "x", "h", "i"],
+                vec!["u", "x", "g", "i"],
+                vec!["u", "x", "h", "i"],
+            ]
+        );
+
+        let mut first = vec![];
         g.all_paths(&"u", |path| {
-            println!("{:?}", path);
+            first.push(path);
             false
         });
+        assert_eq!(first, paths[..1]);
+
+        let limited = |max_count, max_length| {
+            let mut paths = vec![];
+            g.visit_paths(
+                &"u",
+                None,
+                PathLimits {
+                    max_count,
+                    max_length,
+                },
+                |path| {
+                    paths.push(path);
+                    true
+                },
+            );
+            paths
+        };
+
+        assert_eq!(limited(Some(3), None), paths[..3]);
+        assert_eq!(limited(None, Some(6)), paths[2..]);
+        assert_eq!(limited(Some(1), Some(6)), paths[2..3]);
+        assert!(limited(None, Some(3)).is_empty());
+
+        assert_eq!(
+            g.paths_between(&"v", &"x", PathLimits::default()),
+            vec![vec!["v", "y", "w", "z", "x"], vec!["v", "y", "x"]]
+        );
+        assert_eq!(
+            g.paths_between(
+                &"y",
+                &"x",
+                PathLimits {
+                    max_length: Some(3),
+                    ..Default::default()
+                }
+            ),
+            vec![vec!["y", "x"]]
+        );
+        assert_eq!(
+            g.paths_between(&"x", &"x", PathLimits::default()),
+            vec![vec!["x"]]
+        );
+        assert!(g
+            .paths_between(&"x", &"v", PathLimits::default())
+            .is_empty());
+        assert_eq!(g.paths_between(&"i", &"i", PathLimits::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_bidirected_graph() {
+        use crate::gfa::Orientation::{Forward, Reverse};
+
+        let mut g: BidirectedGraph<_> = BidirectedGraph::new();
+        g.add_link(1, Forward, 2, Reverse);
+        g.add_link(2, Reverse, 3, Forward);
+        g.add_link(3, Forward, 1, Forward);
+
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_count(), 3);
+
+        assert!(g.has_link(&1, Forward, &2, Reverse));
+        // the same link walked on the opposite strand
+        assert!(g.has_link(&2, Forward, &1, Reverse));
+        assert!(!g.has_link(&2, Reverse, &1, Forward));
+
+        assert_eq!(g.neighbors(&(2, Reverse)), vec![(3, Forward)]);
+        assert_eq!(g.neighbors(&(2, Forward)), vec![(1, Reverse)]);
+        assert_eq!(g.predecessors(&(3, Forward)), vec![(2, Reverse)]);
+
+        assert_eq!(g.reachable_from(&(1, Forward)).len(), 3);
+        assert_eq!(g.reachable_from(&(1, Reverse)).len(), 3);
+
+        let directed = g.to_directed();
+        assert_eq!(directed.nodes().len(), 6);
+        assert_eq!(directed.edges().len(), 6);
+        assert!(directed.has_edge(&(3, Reverse), &(2, Forward)));
+
+        g.remove_node(&2);
+        assert_eq!(g.edge_count(), 1);
+        assert!(g.neighbors(&(1, Forward)).is_empty());
+    }
+
+    #[test]
+    fn test_csr_graph() {
+        let edges = [(0, 1), (1, 2), (0, 2), (2, 0), (4, 1), (0, 1)];
+
+        let g = CsrGraph::from_edges(&edges);
+        let expected: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&edges);
+
+        assert_eq!(g.nodes(), expected.nodes());
+        assert_eq!(g.edges(), expected.edges());
+        assert_eq!(g.adjacencies(), expected.adjacencies());
+        assert_eq!(g.neighbors_slice(0), &[1, 2]);
+        assert!(g.neighbors_slice(3).is_empty());
+        assert_eq!(g.edge_count(), 5);
+
+        let restricted = g.restricted(&vec![0, 1, 2]);
+        assert_eq!(
+            restricted.edges(),
+            expected.restricted(&vec![0, 1, 2]).edges()
+        );
+        assert_eq!(restricted.nodes(), BTreeSet::from([0, 1, 2]));
+
+        let mut g = g;
+        g.add_edge(3, 4);
+        g.add_edge(3, 4);
+        g.remove_edge(&0, &2);
+        g.remove_node(&1);
+
+        assert_eq!(g.edges(), BTreeSet::from([(2, 0), (3, 4)]));
+        assert_eq!(g.nodes(), BTreeSet::from([0, 2, 3, 4]));
+        assert_eq!(g.node_count(), 4);
+    }
+
+    #[test]
+    fn test_csr_graph_from_bidirected() {
+        use crate::gfa::Orientation::{Forward, Reverse};
+
+        let mut g: BidirectedGraph<String> = BidirectedGraph::new();
+        g.add_link("a".to_string(), Forward, "b".to_string(), Reverse);
+        g.add_link("b".to_string(), Forward, "c".to_string(), Forward);
+
+        let (csr, interner) = CsrGraph::from_bidirected(&g);
+        let directed = g.to_directed();
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!(csr.node_count(), directed.node_count());
+        assert_eq!(csr.edge_count(), directed.edge_count());
+
+        let a = interner.get("a").unwrap();
+        let b = interner.get("b").unwrap();
+        let from = csr::handle(a, Forward);
+
+        assert_eq!(csr.neighbors_slice(from), &[csr::handle(b, Reverse)]);
+        assert_eq!(
+            csr::unpack_handle(csr.neighbors_slice(from)[0]),
+            (b, Reverse)
+        );
+        assert_eq!(interner.resolve(b), "b");
+
+        // the same edges as the directed graph, on the other strand too
+        let edges = csr
+            .edges()
+            .into_iter()
+            .map(|(from, to)| {
+                let ((from, from_orient), (to, to_orient)) =
+                    (csr::unpack_handle(from), csr::unpack_handle(to));
+
+                (
+                    (interner.resolve(from).to_string(), from_orient),
+                    (interner.resolve(to).to_string(), to_orient),
+                )
+            })
+            .collect::<BTreeSet<_>>();
+        assert_eq!(edges, directed.edges());
+
+        // the largest segment index still fits in a handle with its orientation
+        let last = csr::handle(csr::MAX_SEGMENT, Reverse);
+        assert_eq!(last, u32::MAX);
+        assert_eq!(csr::unpack_handle(last), (csr::MAX_SEGMENT, Reverse));
+        assert!(std::panic::catch_unwind(|| csr::handle(csr::MAX_SEGMENT + 1, Forward)).is_err());
+    }
+
+    #[test]
+    fn test_borrowing_iterators() {
+        let edges = [(0, 1), (1, 2), (0, 2), (2, 0), (4, 1)];
+
+        let adjacency = AdjacencyGraph::from_edges(&edges);
+        let dag = DirectedAcyclicGraph::from_edges(&edges);
+        let csr = CsrGraph::from_edges(&edges);
+
+        fn check(g: &impl Graph<u32>) {
+            assert_eq!(g.nodes_iter().copied().collect::<Vec<_>>(), [0, 1, 2, 4]);
+            assert_eq!(
+                g.edges_iter()
+                    .map(|(from, to)| (*from, *to))
+                    .collect::<BTreeSet<_>>(),
+                g.edges()
+            );
+            assert_eq!(g.neighbors_iter(&0).copied().collect::<Vec<_>>(), [1, 2]);
+            assert_eq!(g.neighbors_iter(&3).count(), 0);
+
+            assert_eq!(g.node_count(), 4);
+            assert_eq!(g.edge_count(), 5);
+            assert_eq!(g.out_degree(&0), 2);
+            assert_eq!(g.in_degree(&1), 2);
+            assert_eq!(g.in_degree(&4), 0);
+        }
+
+        check(&adjacency);
+        check(&dag);
+        check(&csr);
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        let mut g: AdjacencyGraph<u32, usize> = AdjacencyGraph::new();
+        g.add_edge_with(0, 1, 5);
+        g.add_edge_with(1, 2, 7);
+        g.add_edge_with(2, 0, 1);
+        g.add_edge(0, 2);
+
+        assert_eq!(g.edge(&0, &1), Some(&5));
+        assert_eq!(g.edge(&0, &2), Some(&0));
+        assert_eq!(g.edge(&1, &0), None);
+
+        // adding an edge again without a payload keeps the previous one
+        g.add_edge(0, 1);
+        assert_eq!(g.edge(&0, &1), Some(&5));
+        g.add_edge_with(0, 1, 3);
+        assert_eq!(g.edge(&0, &1), Some(&3));
+
+        assert_eq!(
+            g.out_edges_iter(&0).collect::<Vec<_>>(),
+            [(&1, &3), (&2, &0)]
+        );
+
+        let restricted = g.restricted(&vec![1, 2]);
+        assert_eq!(restricted.edge(&1, &2), Some(&7));
+        assert_eq!(restricted.edge_count(), 1);
+
+        let dag = g.dag();
+        assert_eq!(dag.edge_count(), 3);
+        assert!(dag
+            .edges_iter()
+            .all(|(from, to)| dag.edge(from, to) == g.edge(from, to)));
+
+        assert_eq!(g.opposite().edge(&2, &1), Some(&7));
+    }
+
+    #[test]
+    fn test_bidirected_graph_payloads() {
+        use crate::gfa::Orientation::{Forward, Reverse};
+
+        let mut g: BidirectedGraph<u32, String> = BidirectedGraph::new();
+        g.add_link_with(1, Forward, 2, Reverse, "3M".to_string());
+        g.add_link(2, Reverse, 3, Forward);
+
+        assert_eq!(g.link(&1, Forward, &2, Reverse), Some(&"3M".to_string()));
+        assert_eq!(g.link(&2, Forward, &1, Reverse), Some(&"3M".to_string()));
+        assert_eq!(g.link(&2, Reverse, &3, Forward), Some(&String::new()));
+
+        let directed = g.to_directed();
+        assert_eq!(
+            directed.edge(&(2, Forward), &(1, Reverse)),
+            Some(&"3M".to_string())
+        );
+
+        // the overlap walked on the opposite strand has insertions and deletions swapped
+        g.add_link_with_twin(
+            3,
+            Forward,
+            4,
+            Forward,
+            "2M1I".to_string(),
+            "1D2M".to_string(),
+        );
+        assert_eq!(g.link(&3, Forward, &4, Forward), Some(&"2M1I".to_string()));
+        assert_eq!(g.link(&4, Reverse, &3, Reverse), Some(&"1D2M".to_string()));
+
+        let directed = g.to_directed();
+        assert_eq!(
+            directed.edge(&(4, Reverse), &(3, Reverse)),
+            Some(&"1D2M".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dag_try_add_edge() {
+        let mut dag: DirectedAcyclicGraph<_> = DirectedAcyclicGraph::new();
+
+        assert_eq!(dag.try_add_edge(0, 1), Ok(()));
+        assert_eq!(dag.try_add_edge(1, 2), Ok(()));
+        assert_eq!(dag.try_add_edge(0, 2), Ok(()));
+
+        // the shortest cycle is reported
+        assert_eq!(
+            dag.try_add_edge(2, 0),
+            Err(CycleError { cycle: vec![2, 0] })
+        );
+        assert_eq!(dag.try_add_edge(1, 1), Err(CycleError { cycle: vec![1] }));
+        assert_eq!(dag.edge_count(), 3);
+
+        let error = dag.try_add_edge(2, 1).unwrap_err();
+        assert_eq!(error.to_string(), "found cycle: 2 -> 1 -> 2");
+    }
+
+    #[test]
+    fn test_dag_try_from() {
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (0, 2)]);
+        let dag = DirectedAcyclicGraph::try_from(g).unwrap();
+        assert_eq!(dag.edge_count(), 3);
+
+        let g: AdjacencyGraph<_> =
+            AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)]);
+        assert_eq!(g.find_cycle(), Some(vec![1, 2, 3]));
+        assert_eq!(g.find_path(&0, &4), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(g.find_path(&4, &0), None);
+
+        let error = DirectedAcyclicGraph::try_from(g).unwrap_err();
+        assert_eq!(error.cycle, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_feedback_arc_sets() {
+        use feedback_arc_set::FeedbackArcSetStrategy;
+
+        // two cycles 0 -> 1 -> 2 -> 0 and 3 -> 4 -> 3 joined by 2 -> 3, plus a self loop on 5
+        let g: AdjacencyGraph<_> =
+            AdjacencyGraph::from_edges(&[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (5, 5)]);
+        let paths = vec![vec![2, 0, 1], vec![4, 3]];
+
+        for strategy in FeedbackArcSetStrategy::ALL {
+            let removed = g.feedback_arc_set(strategy, &paths);
+            assert_eq!(removed.len(), 3, "{}", strategy);
+            assert!(removed.contains(&(5, 5)));
+
+            let dag = g.dag_without(&removed);
+            assert_eq!(dag.edge_count(), 4);
+            assert!(dag.to_adjecency_graph().find_cycle().is_none());
+        }
+
+        let removed = g.feedback_arc_set(FeedbackArcSetStrategy::PathGuided, &paths);
+        assert_eq!(removed, BTreeSet::from([(1, 2), (3, 4), (5, 5)]));
+
+        // the path goes against the initial order of the nodes, so every path edge reorders them
+        let edges = (0..5)
+            .flat_map(|from| (0..5).map(move |to| (from, to)))
+            .filter(|(from, to)| from != to)
+            .collect::<Vec<_>>();
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&edges);
+
+        let removed =
+            g.feedback_arc_set(FeedbackArcSetStrategy::PathGuided, &[vec![4, 3, 2, 1, 0]]);
+        assert_eq!(
+            removed,
+            edges
+                .iter()
+                .copied()
+                .filter(|(from, to)| from < to)
+                .collect()
+        );
+        assert!(g.dag_without(&removed).0.find_cycle().is_none());
+
+        assert_eq!("scc".parse(), Ok(FeedbackArcSetStrategy::SccLocal));
+        assert!("foo".parse::<FeedbackArcSetStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let dag: DirectedAcyclicGraph<_> =
+            DirectedAcyclicGraph::from_edges(&[(3, 1), (1, 2), (0, 2), (4, 0), (3, 4)]);
+
+        let kahn = dag.topological_order();
+        let dfs = dag.topological_order_dfs();
+
+        assert_eq!(kahn, vec![3, 1, 4, 0, 2]);
+        assert_eq!(dfs, vec![3, 4, 1, 0, 2]);
+
+        for order in [kahn, dfs] {
+            let positions = order
+                .iter()
+                .enumerate()
+                .map(|(i, node)| (*node, i))
+                .collect::<BTreeMap<_, _>>();
+
+            assert_eq!(positions.len(), dag.node_count());
+            assert!(dag
+                .edges_iter()
+                .all(|(from, to)| positions[from] < positions[to]));
+        }
+    }
+
+    #[test]
+    fn test_path_guided_sort() {
+        // a bubble 0 -> {1, 4} -> 3 -> 5, each path takes one of the branches
+        let dag: DirectedAcyclicGraph<_> =
+            DirectedAcyclicGraph::from_edges(&[(0, 1), (0, 4), (1, 3), (4, 3), (3, 5)]);
+        let paths = vec![vec![0, 4, 3, 5], vec![0, 1, 3, 5]];
+
+        let layout = dag.path_guided_sort(&paths, |_| 10, 20, 7);
+        let order = layout.iter().map(|(node, _)| *node).collect::<Vec<_>>();
+
+        assert_eq!(order.first(), Some(&0));
+        assert_eq!(&order[3..], &[3, 5]);
+        assert!(layout.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        // same seed, same layout
+        assert_eq!(dag.path_guided_sort(&paths, |_| 10, 20, 7), layout);
+
+        // a step outside of the graph still counts in the distances along the path
+        let dag: DirectedAcyclicGraph<_> = DirectedAcyclicGraph::from_edges(&[(0, 3)]);
+        let layout = dag.path_guided_sort(&[vec![0, 9, 3]], |_| 10, 30, 7);
+
+        assert_eq!(layout[0].0, 0);
+        assert!(
+            (layout[1].1 - layout[0].1 - 20.0).abs() < 1.0,
+            "{:?}",
+            layout
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let g: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&[
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 3),
+            (3, 4),
+            (4, 3),
+            (4, 5),
+            (6, 6),
+        ]);
+
+        let tarjan = g.strongly_connected_components();
+        let kosaraju = g.strongly_connected_components_kosaraju();
+
+        let expected = vec![vec![0, 1, 2], vec![3, 4], vec![5], vec![6]];
+        for components in [&tarjan, &kosaraju] {
+            let mut sorted = components.clone();
+            sorted.sort();
+            assert_eq!(sorted, expected);
+
+            // topological order
+            let position = |node: &i32| components.iter().position(|c| c.contains(node));
+            assert!(position(&0) < position(&3));
+            assert!(position(&3) < position(&5));
+        }
+
+        let (condensation, components) = g.condensation();
+        assert_eq!(components, tarjan);
+        assert_eq!(condensation.node_count(), 4);
+        assert_eq!(condensation.edge_count(), 2);
+
+        let first = components.iter().position(|c| c.contains(&0)).unwrap();
+        let second = components.iter().position(|c| c.contains(&3)).unwrap();
+        assert_eq!(condensation.edge(&first, &second), Some(&1));
+        assert_eq!(condensation.topological_order().len(), 4);
+    }
+
+    #[test]
+    fn test_predecessor_index() {
+        let edges = [(0, 1), (1, 2), (0, 2), (2, 0), (4, 1)];
+
+        let mut indexed: AdjacencyGraph<_> = AdjacencyGraph::from_edges(&edges);
+        indexed.index_predecessors();
+        assert!(indexed.has_predecessor_index());
+
+        let mut scanned = AdjacencyGraph::from_edges(&edges);
+        assert!(!scanned.has_predecessor_index());
+
+        for g in [&mut indexed, &mut scanned] {
+            assert_eq!(g.predecessors(&1), BTreeSet::from([0, 4]));
+            assert_eq!(g.in_degree(&2), 2);
+            assert_eq!(g.in_degree(&4), 0);
+
+            g.add_edge(3, 1);
+            g.remove_edge(&0, &1);
+            assert_eq!(g.predecessors(&1), BTreeSet::from([3, 4]));
+
+            g.remove_node(&2);
+            assert_eq!(g.edges(), BTreeSet::from([(3, 1), (4, 1)]));
+            assert!(g.predecessors(&0).is_empty());
+            assert_eq!(g.in_degree(&1), 2);
+        }
+
+        assert_eq!(indexed.in_degrees(), scanned.in_degrees());
+        assert_eq!(indexed.in_degrees().get(&1), Some(&2));
+        assert_eq!(indexed.compute_ccs().len(), 2);
+        assert_eq!(scanned.compute_ccs().len(), 2);
+    }
+
+    #[test]
+    fn test_superbubbles() {
+        // 1 -> 7 contains the bubble 2 -> 5, then 7 -> 9 is a SNP-like bubble and 9 has a tip
+        let g = DirectedAcyclicGraph::<_>::from_edges(&[
+            (0, 1),
+            (1, 2),
+            (1, 6),
+            (2, 3),
+            (2, 4),
+            (3, 5),
+            (4, 5),
+            (5, 7),
+            (6, 7),
+            (7, 8),
+            (7, 9),
+            (8, 9),
+            (9, 10),
+            (9, 11),
+        ]);
+
+        let bubbles = g.superbubbles();
+        let endpoints = bubbles
+            .iter()
+            .map(|bubble| (bubble.entrance, bubble.exit))
+            .collect::<Vec<_>>();
+        assert_eq!(endpoints, vec![(1, 7), (2, 5), (7, 9)]);
+
+        let mut inside = bubbles[0].inside.clone();
+        inside.sort();
+        assert_eq!(inside, vec![2, 3, 4, 5, 6]);
+        assert_eq!(bubbles[0].size(), 7);
+
+        assert_eq!(bubbles[0].parent, None);
+        assert_eq!(bubbles[1].parent, Some(0));
+        assert_eq!(bubbles[2].parent, None);
+        assert_eq!(
+            bubbles
+                .iter()
+                .map(|bubble| bubble.depth)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 0]
+        );
+
+        // an edge skipping the exit breaks the outer bubble but not the inner one
+        let mut g = g;
+        g.add_edge(6, 8);
+        let endpoints = g
+            .superbubbles()
+            .iter()
+            .map(|bubble| (bubble.entrance, bubble.exit))
+            .collect::<Vec<_>>();
+        assert_eq!(endpoints, vec![(1, 9), (2, 5)]);
+    }
+
+    #[test]
+    fn test_unitigs() {
+        use crate::gfa::{overlap_length, Connection, Orientation::*};
+
+        let mut g: BidirectedGraph<_, String> = BidirectedGraph::new();
+        g.add_link("1", Forward, "2", Reverse);
+        g.add_link_with("2", Reverse, "3", Forward, "1M".to_string());
+        g.add_link("3", Forward, "4", Forward);
+        g.add_link("3", Forward, "5", Forward);
+        // a segment without links is a unitig on its own
+        g.add_node("6");
+
+        assert_eq!(
+            g.unitig_walks(|_| true),
+            vec![
+                vec![("1", Forward), ("2", Reverse), ("3", Forward)],
+                vec![("4", Forward)],
+                vec![("5", Forward)],
+                vec![("6", Forward)],
+            ]
+        );
+
+        let sequences = BTreeMap::from([
+            ("1", "AC"),
+            ("2", "GG"),
+            ("3", "CA"),
+            ("4", "GT"),
+            ("6", "TTA"),
+        ]);
+        let (unitigs, graph) = g.compact_unitigs(
+            |(id, orient)| match orient {
+                Forward => sequences.get(id).unwrap_or(&"").to_string(),
+                Reverse => sequences[id]
+                    .chars()
+                    .rev()
+                    .map(|c| match c {
+                        'A' => 'T',
+                        'C' => 'G',
+                        'G' => 'C',
+                        _ => 'A',
+                    })
+                    .collect(),
+            },
+            |overlap| Some(overlap_length(overlap).unwrap_or(0)),
+        );
+
+        for unitig in unitigs.iter() {
+            let length = g.walk_length(
+                &unitig.segments,
+                |id| sequences.get(id).map_or(0, |sequence| sequence.len()),
+                |overlap| Some(overlap_length(overlap).unwrap_or(0)),
+            );
+            assert_eq!(length, unitig.sequence.len());
+        }
+
+        assert_eq!(unitigs[0].sequence, "ACCCA");
+        assert_eq!(unitigs[1].sequence, "GT");
+        assert_eq!(unitigs[2].sequence, "");
+        assert_eq!(unitigs[3].sequence, "TTA");
+        assert_eq!(
+            graph.edges(),
+            BTreeSet::from([
+                ((0, Side::End), (1, Side::Start)),
+                ((0, Side::End), (2, Side::Start))
+            ])
+        );
+
+        let mut g: BidirectedGraph<_> = BidirectedGraph::new();
+        g.add_link(2, Forward, 1, Reverse);
+        g.add_link(1, Reverse, 0, Forward);
+        g.add_link(0, Forward, 2, Forward);
+
+        // circular walks are cut at their smallest node
+        assert_eq!(
+            g.unitig_walks(|_| true),
+            vec![vec![(0, Forward), (2, Forward), (1, Reverse)]]
+        );
+        let (_, graph) = g.compact_unitigs(|_| String::new(), |_| Some(0));
+        assert_eq!(
+            graph.edges(),
+            BTreeSet::from([((0, Side::Start), (0, Side::End))])
+        );
+
+        // segments across a jump are never merged, the jump stays between the unitigs
+        let mut g: BidirectedGraph<_, Connection> = BidirectedGraph::new();
+        g.add_link_with(
+            "1",
+            Forward,
+            "2",
+            Forward,
+            Connection::Link("0M".to_string()),
+        );
+        g.add_link_with("2", Forward, "3", Forward, Connection::Jump(Some(50)));
+
+        let sequences = BTreeMap::from([("1", "AC"), ("2", "GT"), ("3", "GGTT")]);
+        let (unitigs, graph) = g.compact_unitigs(
+            |(id, _)| sequences[id].to_string(),
+            Connection::overlap_length,
+        );
+
+        assert_eq!(
+            unitigs
+                .iter()
+                .map(|unitig| &unitig.sequence)
+                .collect::<Vec<_>>(),
+            vec!["ACGT", "GGTT"]
+        );
+        assert_eq!(
+            graph.link(&0, Forward, &1, Forward),
+            Some(&Connection::Jump(Some(50)))
+        );
+    }
+
+    #[test]
+    fn test_path_sampling() {
+        let g = DirectedAcyclicGraph::<_>::from_edges(&[
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (2, 3),
+            (3, 4),
+            (3, 5),
+            (6, 3),
+        ]);
+
+        let log_counts = g.log_path_counts();
+        assert_eq!(log_counts[&4], 0.0);
+        assert!((log_counts[&3].exp() - 2.0).abs() < 1e-9);
+        assert!((log_counts[&0].exp() - 4.0).abs() < 1e-9);
+        assert!((g.log_path_count().exp() - 6.0).abs() < 1e-9);
+
+        let samples = g.sample_paths(6000, 42);
+        assert_eq!(samples, g.sample_paths(6000, 42));
+
+        let mut histogram = BTreeMap::new();
+        for path in samples {
+            assert!(path[0] == 0 || path[0] == 6);
+            assert!(path
+                .windows(2)
+                .all(|step| g.edge(&step[0], &step[1]).is_some()));
+            assert_eq!(g.out_degree(path.last().unwrap()), 0);
+
+            *histogram.entry(path).or_insert(0) += 1;
+        }
+
+        // every path is about as likely, even if paths from 0 are twice as many
+        assert_eq!(histogram.len(), 6);
+        assert!(histogram.values().all(|count| (800..1200).contains(count)));
+
+        // a graph with a billion billion paths doesn't overflow
+        let mut g: DirectedAcyclicGraph<_> = DirectedAcyclicGraph::new();
+        for i in 0..60 {
+            g.add_edge(2 * i, 2 * i + 1);
+            g.add_edge(2 * i, 2 * i + 2);
+            g.add_edge(2 * i + 1, 2 * i + 2);
+        }
+
+        assert!((g.log_path_count() - 60.0 * 2f64.ln()).abs() < 1e-9);
+        assert_eq!(g.sample_paths(1, 1)[0].last(), Some(&120));
+    }
+
+    #[test]
+    fn test_find_pattern() {
+        let g = DirectedAcyclicGraph::<_>::from_edges(&[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)]);
+        let sequences = BTreeMap::from([(0, "AC"), (1, "G"), (2, "T"), (3, "TA"), (4, "")]);
+
+        let find = |pattern: &str| {
+            g.find_pattern(
+                pattern.as_bytes(),
+                |node| sequences[node].as_bytes().to_vec(),
+                |_| 0,
+            )
+            .into_iter()
+            .map(|occurrence| (occurrence.start, occurrence.offset, occurrence.path))
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(find("CGT"), vec![(0, 1, vec![0, 1, 3])]);
+        assert_eq!(find("CTT"), vec![(0, 1, vec![0, 2, 3])]);
+        assert_eq!(find("ACGTA"), vec![(0, 0, vec![0, 1, 3])]);
+        assert_eq!(find("T"), vec![(2, 0, vec![2]), (3, 0, vec![3])]);
+        assert_eq!(find("TA"), vec![(3, 0, vec![3])]);
+        assert_eq!(find("A"), vec![(0, 0, vec![0]), (3, 1, vec![3])]);
+        assert!(find("ACGTAA").is_empty());
+        assert!(find("").is_empty());
+
+        // the overlap of an edge is only read once
+        let mut g: DirectedAcyclicGraph<_, usize> = DirectedAcyclicGraph::new();
+        g.add_edge_with(0, 1, 2);
+        let sequences = BTreeMap::from([(0, "ACGT"), (1, "GTAA")]);
+
+        let occurrences = g.find_pattern(
+            b"CGTA",
+            |node| sequences[node].as_bytes().to_vec(),
+            |overlap| *overlap,
+        );
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].path, vec![0, 1]);
+        assert!(g
+            .find_pattern(
+                b"GTGT",
+                |node| sequences[node].as_bytes().to_vec(),
+                |overlap| *overlap
+            )
+            .is_empty());
+
+        // windows starting inside the overlap are only found starting in the predecessor
+        let find = |pattern: &str| {
+            g.find_pattern(
+                pattern.as_bytes(),
+                |node| sequences[node].as_bytes().to_vec(),
+                |overlap| *overlap,
+            )
+            .into_iter()
+            .map(|occurrence| (occurrence.start, occurrence.offset, occurrence.path))
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(find("GT"), vec![(0, 2, vec![0])]);
+        assert_eq!(find("GTA"), vec![(0, 2, vec![0, 1])]);
+        assert_eq!(find("TA"), vec![(0, 3, vec![0, 1])]);
+        assert_eq!(find("AA"), vec![(1, 2, vec![1])]);
     }
 }
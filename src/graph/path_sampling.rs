@@ -0,0 +1,140 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{DirectedAcyclicGraph, Graph};
+
+/// Natural logarithm of the sum of the exponentials of the given values, without overflowing
+fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+
+    max + values
+        .iter()
+        .map(|value| (value - max).exp())
+        .sum::<f64>()
+        .ln()
+}
+
+impl<V, E> DirectedAcyclicGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Natural logarithm of the number of paths from every node to a sink, counts grow
+    /// exponentially with the number of bubbles so they are kept on a log scale. Sinks have
+    /// a single path, so their count is 0.
+    pub fn log_path_counts(&self) -> BTreeMap<V, f64> {
+        let mut log_counts = BTreeMap::new();
+
+        for node in self.topological_order().into_iter().rev() {
+            let log_count = match self.out_degree(&node) {
+                0 => 0.0,
+                _ => log_sum_exp(
+                    &self
+                        .neighbors_iter(&node)
+                        .map(|adj| log_counts[adj])
+                        .collect::<Vec<_>>(),
+                ),
+            };
+
+            log_counts.insert(node, log_count);
+        }
+
+        log_counts
+    }
+
+    /// Natural logarithm of the number of paths from a source to a sink
+    pub fn log_path_count(&self) -> f64 {
+        let log_counts = self.log_path_counts();
+
+        log_sum_exp(
+            &self
+                .sources()
+                .into_iter()
+                .map(|source| log_counts[source])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Samples `count` paths from a source to a sink, each one is picked uniformly among all
+    /// of them (with replacement). The result only depends on `seed`.
+    pub fn sample_paths(&self, count: usize, seed: u64) -> Vec<Vec<V>> {
+        let log_counts = self.log_path_counts();
+        let sources = self.sources();
+        let log_total = log_sum_exp(
+            &sources
+                .iter()
+                .map(|source| log_counts[*source])
+                .collect::<Vec<_>>(),
+        );
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut paths = Vec::with_capacity(count);
+
+        if sources.is_empty() {
+            return paths;
+        }
+
+        for _ in 0..count {
+            // every step picks the next node with probability proportional to the number of
+            // paths through it, so every full path has the same probability
+            let mut node = pick(&mut rng, sources.iter().copied(), log_total, &log_counts);
+            let mut path = vec![node.clone()];
+
+            while self.out_degree(node) > 0 {
+                node = pick(
+                    &mut rng,
+                    self.neighbors_iter(node),
+                    log_counts[node],
+                    &log_counts,
+                );
+                path.push(node.clone());
+            }
+
+            paths.push(path);
+        }
+
+        paths
+    }
+
+    /// Nodes without incoming edges
+    fn sources(&self) -> Vec<&V> {
+        let has_predecessor = self.edges_iter().map(|(_, to)| to).collect::<BTreeSet<_>>();
+
+        self.nodes_iter()
+            .filter(|node| !has_predecessor.contains(node))
+            .collect()
+    }
+}
+
+/// Picks one of `candidates` with probability proportional to its path count, `log_total` is
+/// the log of the sum of their counts
+fn pick<'a, V: Ord>(
+    rng: &mut StdRng,
+    candidates: impl Iterator<Item = &'a V>,
+    log_total: f64,
+    log_counts: &BTreeMap<V, f64>,
+) -> &'a V {
+    let target = rng.gen::<f64>();
+    let mut cumulative = 0.0;
+    let mut last = None;
+
+    for candidate in candidates {
+        cumulative += (log_counts[candidate] - log_total).exp();
+        last = Some(candidate);
+
+        if target < cumulative {
+            return candidate;
+        }
+    }
+
+    // rounding errors can leave the cumulative probability just below 1
+    last.expect("at least one candidate")
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use super::{AdjacencyGraph, DirectedAcyclicGraph, Graph};
+
+impl<V, E> AdjacencyGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Strongly connected components using Tarjan's algorithm, each component is sorted and the
+    /// components come in topological order (no edge goes from a component to a previous one)
+    pub fn strongly_connected_components(&self) -> Vec<Vec<V>> {
+        self.tarjan_components()
+            .into_iter()
+            .rev()
+            .map(|component| {
+                let mut component = component.into_iter().cloned().collect::<Vec<_>>();
+                component.sort();
+                component
+            })
+            .collect()
+    }
+
+    /// Same as [AdjacencyGraph::strongly_connected_components] but with Kosaraju's algorithm,
+    /// a DFS on the graph followed by one on the reversed graph
+    pub fn strongly_connected_components_kosaraju(&self) -> Vec<Vec<V>> {
+        let mut visited = BTreeSet::new();
+        let mut post_order = Vec::new();
+
+        for start in self.nodes.iter() {
+            if !visited.insert(start) {
+                continue;
+            }
+
+            let mut stack = vec![(start, self.neighbors_iter(start))];
+
+            while let Some((node, neighbors)) = stack.last_mut() {
+                let node = *node;
+
+                match neighbors.next() {
+                    Some(adj) => {
+                        if visited.insert(adj) {
+                            stack.push((adj, self.neighbors_iter(adj)));
+                        }
+                    }
+                    None => {
+                        post_order.push(node);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        let mut reverse: BTreeMap<&V, Vec<&V>> = BTreeMap::new();
+        for (from, to) in self.edges_iter() {
+            reverse.entry(to).or_default().push(from);
+        }
+
+        let mut assigned = BTreeSet::new();
+        let mut components = Vec::new();
+
+        for start in post_order.into_iter().rev() {
+            if !assigned.insert(start) {
+                continue;
+            }
+
+            let mut component = vec![start.clone()];
+            let mut stack = vec![start];
+
+            while let Some(node) = stack.pop() {
+                for adj in reverse.get(node).into_iter().flatten() {
+                    if assigned.insert(adj) {
+                        component.push((*adj).clone());
+                        stack.push(adj);
+                    }
+                }
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Graph with a node for each strongly connected component and an edge between two
+    /// components if there is an edge between their nodes, the payload is the number of such
+    /// edges. Node `i` of the condensation is the `i`-th of the returned components, they are
+    /// numbered in topological order.
+    pub fn condensation(&self) -> (DirectedAcyclicGraph<usize, usize>, Vec<Vec<V>>) {
+        let components = self.strongly_connected_components();
+
+        let component_of = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, component)| component.iter().map(move |node| (node, i)))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut condensation = DirectedAcyclicGraph::new();
+
+        for i in 0..components.len() {
+            condensation.add_node(i);
+        }
+
+        for (from, to) in self.edges_iter() {
+            let (from, to) = (component_of[from], component_of[to]);
+
+            if from != to {
+                let count = condensation.edge(&from, &to).copied().unwrap_or(0);
+                condensation.add_edge_with(from, to, count + 1);
+            }
+        }
+
+        (condensation, components)
+    }
+
+    /// Strongly connected components with an iterative version of Tarjan's algorithm, the
+    /// components come in reverse topological order of the condensation
+    pub(super) fn tarjan_components(&self) -> Vec<Vec<&V>> {
+        let mut indices: BTreeMap<&V, usize> = BTreeMap::new();
+        let mut lowlinks: BTreeMap<&V, usize> = BTreeMap::new();
+        let mut stack = Vec::new();
+        let mut on_stack = BTreeSet::new();
+        let mut components = Vec::new();
+
+        for start in self.nodes.iter() {
+            if indices.contains_key(start) {
+                continue;
+            }
+
+            indices.insert(start, indices.len());
+            lowlinks.insert(start, indices[start]);
+            stack.push(start);
+            on_stack.insert(start);
+
+            let mut calls = vec![(start, self.neighbors_iter(start))];
+
+            while let Some((node, neighbors)) = calls.last_mut() {
+                let node = *node;
+
+                match neighbors.next() {
+                    Some(adj) if !indices.contains_key(adj) => {
+                        indices.insert(adj, indices.len());
+                        lowlinks.insert(adj, indices[adj]);
+                        stack.push(adj);
+                        on_stack.insert(adj);
+
+                        calls.push((adj, self.neighbors_iter(adj)));
+                    }
+                    Some(adj) => {
+                        if on_stack.contains(adj) {
+                            let lowlink = lowlinks[node].min(indices[adj]);
+                            lowlinks.insert(node, lowlink);
+                        }
+                    }
+                    None => {
+                        calls.pop();
+
+                        if let Some((parent, _)) = calls.last() {
+                            let lowlink = lowlinks[parent].min(lowlinks[node]);
+                            lowlinks.insert(parent, lowlink);
+                        }
+
+                        if lowlinks[node] == indices[node] {
+                            let mut component = Vec::new();
+
+                            while let Some(other) = stack.pop() {
+                                on_stack.remove(other);
+                                component.push(other);
+
+                                if other == node {
+                                    break;
+                                }
+                            }
+
+                            components.push(component);
+                        }
+                    }
+                }
+            }
+        }
+
+        components
+    }
+}
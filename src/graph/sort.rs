@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::DirectedAcyclicGraph;
+
+impl<V, E> DirectedAcyclicGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Sorts the nodes on a line using the paths, like the path-guided stochastic gradient
+    /// descent of `odgi sort`. Nodes start at their position in the topological order (in bases,
+    /// using `node_length`), then each update takes two steps of a random path and moves them so
+    /// their distance gets closer to their distance along the path.
+    ///
+    /// Returns the nodes with their position, sorted by position. The result only depends on
+    /// `seed`, nodes not in any path keep their starting position.
+    pub fn path_guided_sort<F>(
+        &self,
+        paths: &[Vec<V>],
+        node_length: F,
+        iterations: usize,
+        seed: u64,
+    ) -> Vec<(V, f64)>
+    where
+        F: Fn(&V) -> usize,
+    {
+        let order = self.topological_order();
+        let indices = order
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node, i))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut positions = Vec::with_capacity(order.len());
+        let mut offset = 0.0;
+        for node in order.iter() {
+            positions.push(offset);
+            offset += node_length(node) as f64;
+        }
+
+        // steps of each path in the graph as (node index, offset along the path), the steps
+        // outside of the graph are dropped but still count in the offsets
+        let paths = paths
+            .iter()
+            .map(|path| {
+                let mut offset = 0;
+
+                path.iter()
+                    .filter_map(|node| {
+                        let step = indices.get(node).map(|index| (*index, offset as f64));
+                        offset += node_length(node);
+                        step
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|steps| steps.len() > 1)
+            .collect::<Vec<_>>();
+
+        // index of the first step of each path among the steps of all the paths
+        let mut path_starts = Vec::with_capacity(paths.len());
+        let mut step_count = 0;
+        for steps in paths.iter() {
+            path_starts.push(step_count);
+            step_count += steps.len();
+        }
+
+        let max_distance = paths
+            .iter()
+            .map(|steps| steps.last().map_or(0.0, |(_, offset)| *offset))
+            .fold(1.0, f64::max);
+
+        if step_count == 0 || iterations == 0 {
+            return order.into_iter().zip(positions).collect();
+        }
+
+        // the learning rate decays exponentially from max_distance^2 to 0.01 like in the
+        // original SGD graph drawing algorithm
+        let eta_max = max_distance * max_distance;
+        let eta_min = 0.01;
+        let decay = (eta_min / eta_max).ln() / (iterations.max(2) - 1) as f64;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for iteration in 0..iterations {
+            let eta = eta_max * (decay * iteration as f64).exp();
+
+            for _ in 0..step_count {
+                // picking a random step of all the paths, so longer paths are picked more often
+                let k = rng.gen_range(0..step_count);
+                let path = path_starts.partition_point(|start| *start <= k) - 1;
+                let steps = &paths[path];
+
+                let (i, i_offset) = steps[k - path_starts[path]];
+                let (j, j_offset) = steps[rng.gen_range(0..steps.len())];
+
+                if i == j {
+                    continue;
+                }
+
+                let distance = (i_offset - j_offset).abs().max(1.0);
+                let mu = (eta / (distance * distance)).min(1.0);
+
+                let diff = positions[i] - positions[j];
+                let magnitude = diff.abs().max(1e-9);
+                let delta = mu * (magnitude - distance) / 2.0 * diff / magnitude;
+
+                positions[i] -= delta;
+                positions[j] += delta;
+            }
+        }
+
+        let mut layout = order.into_iter().zip(positions).collect::<Vec<_>>();
+        layout.sort_by(|(a, x), (b, y)| x.total_cmp(y).then_with(|| a.cmp(b)));
+
+        layout
+    }
+}
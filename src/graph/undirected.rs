@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     fmt::Debug,
 };
@@ -7,9 +8,10 @@ use indicatif::ProgressBar;
 
 use super::{AdjacencyGraph, Graph, UndirectedGraph};
 
-impl<V> Graph<V> for UndirectedGraph<V>
+impl<V, E> Graph<V, E> for UndirectedGraph<V, E>
 where
     V: Ord + Clone,
+    E: Clone + Default,
 {
     fn new() -> Self
     where
@@ -18,8 +20,8 @@ where
         UndirectedGraph(AdjacencyGraph::new())
     }
 
-    fn to_adjecency_graph(&self) -> &AdjacencyGraph<V> {
-        &self.0
+    fn to_adjecency_graph(&self) -> Cow<'_, AdjacencyGraph<V, E>> {
+        Cow::Borrowed(&self.0)
     }
 
     fn nodes(&self) -> BTreeSet<V> {
@@ -38,13 +40,62 @@ where
         self.0.edges()
     }
 
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.0.nodes_iter()
+    }
+
+    fn neighbors_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = &'a V>
+    where
+        V: 'a,
+    {
+        self.0.neighbors_iter(from)
+    }
+
+    fn out_edges_iter<'a>(&'a self, from: &V) -> impl Iterator<Item = (&'a V, &'a E)>
+    where
+        V: 'a,
+        E: 'a,
+    {
+        self.0.out_edges_iter(from)
+    }
+
+    fn edge<'a>(&'a self, from: &V, to: &V) -> Option<&'a E>
+    where
+        V: 'a,
+    {
+        self.0.edge(from, to)
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = (&'a V, &'a V)>
+    where
+        V: 'a,
+    {
+        self.0.edges_iter()
+    }
+
+    fn node_count(&self) -> usize {
+        self.0.node_count()
+    }
+
+    fn out_degree(&self, node: &V) -> usize {
+        self.0.out_degree(node)
+    }
+
+    fn in_degree(&self, node: &V) -> usize {
+        self.0.in_degree(node)
+    }
+
     fn add_node(&mut self, node: V) {
         self.0.add_node(node);
     }
 
-    fn add_edge(&mut self, from: V, to: V) {
-        self.0.add_edge(from.clone(), to.clone());
-        self.0.add_edge(to, from);
+    fn add_edge_with(&mut self, from: V, to: V, payload: E) {
+        self.0
+            .add_edge_with(from.clone(), to.clone(), payload.clone());
+        self.0.add_edge_with(to, from, payload);
     }
 
     fn remove_node(&mut self, node: &V) {
@@ -57,9 +108,10 @@ where
     }
 }
 
-impl<V> UndirectedGraph<V>
+impl<V, E> UndirectedGraph<V, E>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
     pub fn add_edge(&mut self, from: V, to: V) {
         self.0.add_edge(from.clone(), to.clone());
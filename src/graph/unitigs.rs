@@ -0,0 +1,208 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use crate::gfa::Orientation;
+
+use super::{BidirectedGraph, Side};
+
+/// Maximal non-branching walk of a bidirected graph merged in a single segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unitig<V> {
+    /// Original segments in the order they are walked, with the orientation they are read in
+    pub segments: Vec<(V, Orientation)>,
+    /// Concatenation of the oriented sequences of the segments, without the overlaps
+    pub sequence: String,
+}
+
+impl<V, E> BidirectedGraph<V, E>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    /// Splits the nodes in maximal non-branching walks, every node is in exactly one of them.
+    /// A walk goes on from a node only if the side it leaves from has a single link, the side
+    /// on the other end has no other link and `joins` accepts the payload of the link (e.g. not
+    /// across a gap). Walks are returned in order of their smallest node and oriented so that
+    /// node is read forward.
+    pub fn unitig_walks<J>(&self, joins: J) -> Vec<Vec<(V, Orientation)>>
+    where
+        J: Fn(&E) -> bool,
+    {
+        let mut visited = BTreeSet::new();
+        let mut walks = Vec::new();
+
+        for node in self.nodes.iter() {
+            if visited.contains(node) {
+                continue;
+            }
+
+            visited.insert(node.clone());
+
+            let forward =
+                self.extend_walk(&(node.clone(), Orientation::Forward), &joins, &mut visited);
+            let backward =
+                self.extend_walk(&(node.clone(), Orientation::Reverse), &joins, &mut visited);
+
+            let mut walk = backward
+                .into_iter()
+                .rev()
+                .map(|(id, orient)| (id, orient.flip()))
+                .collect::<Vec<_>>();
+            walk.push((node.clone(), Orientation::Forward));
+            walk.extend(forward);
+
+            walks.push(walk);
+        }
+
+        walks
+    }
+
+    /// Nodes that follow `start` without branching, not including `start`
+    fn extend_walk<J>(
+        &self,
+        start: &(V, Orientation),
+        joins: &J,
+        visited: &mut BTreeSet<V>,
+    ) -> Vec<(V, Orientation)>
+    where
+        J: Fn(&E) -> bool,
+    {
+        let mut walk = Vec::new();
+        let mut current = start.clone();
+
+        loop {
+            let mut links = self.out_links(&current);
+            let next = match (links.next(), links.next()) {
+                (Some((next, payload)), None) if joins(payload) => next,
+                _ => break,
+            };
+
+            let entry = (next.0.clone(), Side::entry(next.1));
+            if self.side_neighbors(&entry).count() != 1 || visited.contains(&next.0) {
+                break;
+            }
+
+            visited.insert(next.0.clone());
+            walk.push(next.clone());
+            current = next;
+        }
+
+        walk
+    }
+
+    /// Length of the sequence [BidirectedGraph::compact_unitigs] merges for `walk`, without
+    /// building it. `length` gives the length of a segment and `overlap` the length of the
+    /// overlap of a link, as in [BidirectedGraph::compact_unitigs].
+    pub fn walk_length<F, G>(&self, walk: &[(V, Orientation)], length: F, overlap: G) -> usize
+    where
+        F: Fn(&V) -> usize,
+        G: Fn(&E) -> Option<usize>,
+    {
+        walk.iter()
+            .enumerate()
+            .map(|(position, (node, orient))| {
+                let skipped = match position {
+                    0 => 0,
+                    _ => {
+                        let (prev, prev_orient) = &walk[position - 1];
+
+                        self.link(prev, *prev_orient, node, *orient)
+                            .and_then(&overlap)
+                            .unwrap_or(0)
+                    }
+                };
+
+                length(node).saturating_sub(skipped)
+            })
+            .sum()
+    }
+
+    /// Merges every maximal non-branching walk in a single segment. `sequence` gives the
+    /// sequence of an oriented segment (reverse complemented if needed) and `overlap` the
+    /// length of the overlap of a link, that is skipped when concatenating. Links with no
+    /// overlap length (`None`, e.g. jumps over a gap) are never merged.
+    ///
+    /// Returns the unitigs and the graph of the links between them, unitig `i` is node `i` and
+    /// its start and end sides are the sides its first and last segments are walked from.
+    pub fn compact_unitigs<F, G>(
+        &self,
+        sequence: F,
+        overlap: G,
+    ) -> (Vec<Unitig<V>>, BidirectedGraph<usize, E>)
+    where
+        F: Fn(&(V, Orientation)) -> String,
+        G: Fn(&E) -> Option<usize>,
+    {
+        let walks = self.unitig_walks(|payload| overlap(payload).is_some());
+
+        // unitig, position and orientation of every node
+        let mut locations = BTreeMap::new();
+
+        let unitigs = walks
+            .into_iter()
+            .enumerate()
+            .map(|(i, segments)| {
+                let mut merged = String::new();
+
+                for (position, node) in segments.iter().enumerate() {
+                    locations.insert(node.0.clone(), (i, position, segments.len(), node.1));
+
+                    // the overlap with the previous segment is already in the sequence
+                    let skipped = match position {
+                        0 => 0,
+                        _ => {
+                            let (prev, prev_orient) = &segments[position - 1];
+
+                            self.adjacencies
+                                .get(&(prev.clone(), Side::exit(*prev_orient)))
+                                .and_then(|sides| sides.get(&(node.0.clone(), Side::entry(node.1))))
+                                .and_then(&overlap)
+                                .unwrap_or(0)
+                        }
+                    };
+
+                    let piece = sequence(node);
+
+                    merged.push_str(&piece[skipped.min(piece.len())..]);
+                }
+
+                Unitig {
+                    segments,
+                    sequence: merged,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // only the sides at the ends of a unitig are still linked to other sides
+        let unitig_side = |(node, side): &(V, Side)| {
+            let (i, position, len, orient) = locations[node];
+
+            if position == 0 && *side == Side::entry(orient) {
+                Some((i, Side::Start))
+            } else if position == len - 1 && *side == Side::exit(orient) {
+                Some((i, Side::End))
+            } else {
+                None
+            }
+        };
+
+        let mut graph = BidirectedGraph::new();
+
+        for i in 0..unitigs.len() {
+            graph.add_node(i);
+        }
+
+        for (a, sides) in self.adjacencies.iter() {
+            for (b, payload) in sides.range(a..) {
+                if let (Some(unitig_a), Some(unitig_b)) = (unitig_side(a), unitig_side(b)) {
+                    let twin = self.adjacencies[b][a].clone();
+                    graph.add_edge_with_twin(unitig_a, unitig_b, payload.clone(), twin);
+                }
+            }
+        }
+
+        (unitigs, graph)
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+mod fasta;
 mod gfa;
 mod graph;
 mod rolling_hash;
@@ -7,28 +8,73 @@ mod rolling_hash;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Debug,
-    io::{BufRead, BufReader},
+    fs::File,
+    io::{self, BufWriter, Write},
     process,
 };
 
 use argh::FromArgs;
-use gfa::{Entry, Orientation};
-use graph::{AdjacencyGraph, DirectedAcyclicGraph, Graph};
+use gfa::{parser::GfaReader, Connection, Entry, GfaError, Orientation};
+use graph::{
+    csr::{self, Interner},
+    feedback_arc_set::FeedbackArcSetStrategy,
+    AdjacencyGraph, BidirectedGraph, CsrGraph, DirectedAcyclicGraph, Graph, PathLimits,
+};
 use indicatif::ProgressIterator;
 use rand::seq::SliceRandom;
 use rolling_hash::RollingHasher;
 
+/// Parameters of the path-guided sort used for `--output-layout`
+const LAYOUT_ITERATIONS: usize = 30;
+const LAYOUT_SEED: u64 = 42;
+
 #[derive(FromArgs, PartialEq, Debug)]
 /// Strumento CLI per il progetto di Algoritmi e Strutture Dati 2024
 struct CliTool {
     #[argh(option, short = 'i')]
-    /// file to read
+    /// file to read, use "-" to read from stdin
     input: String,
 
+    #[argh(option, short = 'o')]
+    /// file to write the stats and reports to (default: stdout)
+    output: Option<String>,
+
+    #[argh(option)]
+    /// file to save the largest connected component of the DAG to, as GFA
+    output_gfa: Option<String>,
+
+    #[argh(option)]
+    /// file to save the 1D layout of the largest connected component to, as TSV with the
+    /// position of every oriented segment sorted using the paths
+    output_layout: Option<String>,
+
+    #[argh(option)]
+    /// file to save the unitigs (maximal non-branching walks merged in a single segment) of the
+    /// graph to, as GFA
+    output_unitigs: Option<String>,
+
+    #[argh(option)]
+    /// file to save the sequences of the paths and walks (P and W lines) to, as FASTA
+    output_fasta: Option<String>,
+
     #[argh(option, short = 'c', default = "1")]
     /// number of paths to visit
     path_count: usize,
 
+    #[argh(option)]
+    /// maximum number of segments of the visited paths, longer paths are skipped (not used with
+    /// --sample-paths)
+    max_path_length: Option<usize>,
+
+    #[argh(switch)]
+    /// sample the visited paths uniformly among all the source to sink paths instead of taking
+    /// the first ones in DFS order
+    sample_paths: bool,
+
+    #[argh(option, default = "42")]
+    /// seed of the random path sampling
+    seed: u64,
+
     #[argh(option, short = 'p', default = "\"ACGT\".to_string()")]
     /// k-mer pattern to search
     pattern: String,
@@ -36,6 +82,23 @@ struct CliTool {
     #[argh(option, short = 'k', default = "4")]
     /// k-mer length
     kmer_size: usize,
+
+    #[argh(switch)]
+    /// skip malformed GFA lines instead of stopping at the first one
+    lenient: bool,
+
+    #[argh(switch)]
+    /// add the jumps (J lines) to the graph as edges
+    jumps: bool,
+
+    #[argh(option, default = "FeedbackArcSetStrategy::Dfs")]
+    /// how to pick the edges removed to make the graph acyclic: dfs, els, scc or paths (keeps
+    /// the edges traversed by P and W lines)
+    feedback_arc_set: FeedbackArcSetStrategy,
+
+    #[argh(switch)]
+    /// report how many edges every feedback arc set strategy removes
+    compare_feedback_arc_sets: bool,
 }
 
 fn main() -> std::io::Result<()> {
@@ -47,73 +110,219 @@ fn main() -> std::io::Result<()> {
         process::exit(1);
     }
 
-    println!("Estimating line count...");
-
-    let file_lines_count = BufReader::new(std::fs::File::open(&opts.input)?)
-        .lines()
-        .progress_with(indicatif::ProgressBar::new_spinner())
-        .count() as u64;
-
-    let entries = gfa::parser::parse_source(std::fs::File::open(opts.input)?, file_lines_count)?;
-    println!("Number of entries: {}", entries.len());
-    println!();
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
 
     let mut sequence_map = HashMap::new();
-    let mut graph: AdjacencyGraph<(String, Orientation)> = AdjacencyGraph::new();
+    // every link carries the CIGAR string of its overlap, every jump its distance
+    let mut bidirected_graph: BidirectedGraph<String, Connection> = BidirectedGraph::new();
 
     let mut invalid_nodes = BTreeSet::new();
+    let mut late_invalid_nodes = Vec::new();
+    // segments that are not valid DNA (e.g. with an N) are left out of the graph, their
+    // sequences and links are only kept to write the sequences of the paths through them
+    let keep_invalid_nodes = opts.output_fasta.is_some();
+    let mut invalid_sequences = HashMap::new();
+    let mut invalid_links: BidirectedGraph<String, Connection> = BidirectedGraph::new();
+
+    let mut entry_count = 0;
+    // oriented segments of the P and W lines, with their names, only kept if something uses them
+    let keep_paths = opts.feedback_arc_set == FeedbackArcSetStrategy::PathGuided
+        || opts.compare_feedback_arc_sets
+        || opts.output_layout.is_some()
+        || opts.output_fasta.is_some();
+    let mut path_count = 0;
+    let mut paths = Vec::new();
+    let mut path_names = Vec::new();
+    // overlaps given by the P lines, empty if not given
+    let mut path_overlaps = Vec::new();
+
+    println!("Parsing GFA file...");
+
+    let mut reader = GfaReader::new(gfa::parser::open_input(&opts.input)?);
+    for entry in &mut reader {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error @ GfaError::Parse { .. }) if opts.lenient => {
+                eprintln!("Skipped malformed {}", error);
+                continue;
+            }
+            // the message of a parse error says where the problem is, the Debug output of the
+            // wrapping io::Error would not
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            }
+        };
+
+        entry_count += 1;
 
-    println!("Populating nodes...");
-    for entry in &entries {
         match entry {
-            Entry::Segment { id, sequence } => {
+            Entry::Segment { id, sequence, .. } => {
                 // validate sequence is a valid DNA sequence
                 if sequence.chars().any(|c| !"ACGT".contains(c)) {
-                    invalid_nodes.insert(id.clone());
+                    // links to this segment could have already been added to the graph
+                    if bidirected_graph.nodes().contains(&id) {
+                        late_invalid_nodes.push(id.clone());
+                    }
+
+                    if keep_invalid_nodes && sequence != "*" {
+                        invalid_sequences.insert(id.clone(), sequence);
+                    }
+
+                    invalid_nodes.insert(id);
                     continue;
                 }
 
-                sequence_map.insert(id.clone(), sequence.clone());
+                // segments without links are still nodes of the graph
+                bidirected_graph.add_node(id.clone());
+                sequence_map.insert(id, sequence);
             }
-            _ => {}
-        }
-    }
-    println!("Number of nodes: {}", sequence_map.len());
-    println!();
-
-    println!("Populating edges...");
-    for entry in &entries {
-        match entry {
             Entry::Link {
                 from,
                 from_orient,
                 to,
                 to_orient,
+                overlap,
+                ..
             } => {
-                if invalid_nodes.contains(from) || invalid_nodes.contains(to) {
+                let overlap = Connection::Link(overlap);
+                let twin = overlap.reversed();
+
+                if invalid_nodes.contains(&from) || invalid_nodes.contains(&to) {
+                    if keep_invalid_nodes {
+                        invalid_links.add_link_with_twin(
+                            from,
+                            from_orient,
+                            to,
+                            to_orient,
+                            overlap,
+                            twin,
+                        );
+                    }
+
+                    continue;
+                }
+
+                bidirected_graph.add_link_with_twin(
+                    from,
+                    from_orient,
+                    to,
+                    to_orient,
+                    overlap,
+                    twin,
+                );
+            }
+            Entry::Jump {
+                from,
+                from_orient,
+                to,
+                to_orient,
+                distance,
+                ..
+            } if opts.jumps => {
+                if invalid_nodes.contains(&from) || invalid_nodes.contains(&to) {
                     continue;
                 }
 
-                graph.add_edge(
-                    (from.clone(), from_orient.clone()),
-                    (to.clone(), to_orient.clone()),
+                // the distance of a jump is the same on both strands
+                bidirected_graph.add_link_with(
+                    from,
+                    from_orient,
+                    to,
+                    to_orient,
+                    Connection::Jump(distance),
                 );
             }
+            Entry::Path {
+                name,
+                segments,
+                overlaps,
+                ..
+            } => {
+                path_count += 1;
+
+                if keep_paths {
+                    path_names.push(name);
+                    paths.push(segments);
+                    path_overlaps.push(overlaps);
+                }
+            }
+            Entry::Walk {
+                sample,
+                haplotype_index,
+                seq_id,
+                seq_start,
+                seq_end,
+                segments,
+                ..
+            } => {
+                path_count += 1;
+
+                if keep_paths {
+                    path_names.push(format!(
+                        "{}#{}#{}:{}-{}",
+                        sample, haplotype_index, seq_id, seq_start, seq_end
+                    ));
+                    paths.push(segments);
+                    path_overlaps.push(Vec::new());
+                }
+            }
             _ => {}
         }
     }
-    println!("Number of edges: {}", graph.edges().len());
-    println!();
 
-    println!("Sequences stats:");
+    reader.print_skipped();
+
+    for id in late_invalid_nodes {
+        if keep_invalid_nodes {
+            for orient in [Orientation::Forward, Orientation::Reverse] {
+                let node = (id.clone(), orient);
+
+                for ((to, to_orient), overlap) in bidirected_graph.out_links(&node) {
+                    let twin = bidirected_graph
+                        .link(&to, to_orient.flip(), &id, orient.flip())
+                        .cloned()
+                        .unwrap_or_default();
+
+                    invalid_links.add_link_with_twin(
+                        id.clone(),
+                        orient,
+                        to,
+                        to_orient,
+                        overlap.clone(),
+                        twin,
+                    );
+                }
+            }
+        }
+
+        bidirected_graph.remove_node(&id);
+    }
+
+    writeln!(out, "Number of entries: {}", entry_count)?;
+    writeln!(out, "Number of paths: {}", path_count)?;
+    writeln!(out, "Number of nodes: {}", sequence_map.len())?;
+    writeln!(out, "Number of links: {}", bidirected_graph.edge_count())?;
+
+    // every link can be walked on both strands, the graph on oriented segments is only built
+    // with u32 handles so that the whole graph passes don't copy the segment ids
+    let (csr_graph, interner) = CsrGraph::from_bidirected(&bidirected_graph);
+    writeln!(out, "Number of edges: {}", csr_graph.edge_count())?;
+    writeln!(out)?;
+
+    writeln!(out, "Sequences stats:")?;
     let mut sequence_lengths: Vec<_> = sequence_map.values().map(|seq| seq.len()).collect();
     sequence_lengths.sort();
 
     let sequence_lengths_average =
         sequence_lengths.iter().sum::<usize>() as f64 / sequence_lengths.len() as f64;
 
-    println!("- Average length: {:.2}", sequence_lengths_average);
-    println!(
+    writeln!(out, "- Average length: {:.2}", sequence_lengths_average)?;
+    writeln!(
+        out,
         "- Standard deviation: {:.2}",
         (sequence_lengths
             .iter()
@@ -121,26 +330,29 @@ fn main() -> std::io::Result<()> {
             .sum::<f64>()
             / sequence_lengths.len() as f64)
             .sqrt()
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "- Max length: {}",
         sequence_lengths
             .iter()
             .max()
             .expect("at least one sequence")
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "- Min length: {}",
         sequence_lengths
             .iter()
             .min()
             .expect("at least one sequence")
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "- Median length: {}",
         sequence_lengths[sequence_lengths.len() / 2]
-    );
-    println!();
+    )?;
+    writeln!(out)?;
 
     let sequence_lengths_histogram: BTreeMap<usize, usize> =
         sequence_lengths
@@ -150,19 +362,76 @@ fn main() -> std::io::Result<()> {
                 acc
             });
 
-    println!("Sequence lengths histogram (length/count):");
+    writeln!(out, "Sequence lengths histogram (length/count):")?;
     for (length, count) in sequence_lengths_histogram.iter() {
-        println!("- {}: {}", length, count);
+        writeln!(out, "- {}: {}", length, count)?;
+    }
+    writeln!(out)?;
+
+    compute_unitigs(
+        &bidirected_graph,
+        &sequence_map,
+        opts.output_unitigs.as_deref(),
+        &mut out,
+    )?;
+
+    if let Some(path) = &opts.output_fasta {
+        compute_path_sequences(
+            |id| sequence_map.get(id).or_else(|| invalid_sequences.get(id)),
+            |(from, from_orient), (to, to_orient)| {
+                bidirected_graph
+                    .link(from, *from_orient, to, *to_orient)
+                    .or_else(|| invalid_links.link(from, *from_orient, to, *to_orient))
+            },
+            &paths,
+            &path_names,
+            &path_overlaps,
+            path,
+            &mut out,
+        )?;
+    }
+
+    // the graph has both strands, so the paths are also walked backwards
+    let stranded_paths = stranded_paths(&paths);
+    let handle_paths = stranded_paths
+        .iter()
+        .map(|path| {
+            path.iter()
+                .filter_map(|(id, orient)| Some(csr::handle(interner.get(id)?, *orient)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    compute_graph_degrees(&csr_graph, &mut out)?;
+
+    // the passes that need the adjacencies in both directions work on a copy with the same
+    // handles, the compact graph is not needed anymore
+    let graph = csr_graph.to_adjecency_graph().into_owned();
+    drop(csr_graph);
+
+    compute_sccs(&graph, &mut out)?;
+
+    if opts.compare_feedback_arc_sets {
+        compute_feedback_arc_sets(&graph, &handle_paths, &mut out)?;
     }
-    println!();
 
-    compute_graph_degrees(&graph);
+    println!("Computing feedback arc set ({})...", opts.feedback_arc_set);
+    let removed_edges = graph.feedback_arc_set(opts.feedback_arc_set, &handle_paths);
+    writeln!(
+        out,
+        "Feedback arc set ({}): removed {} edges",
+        opts.feedback_arc_set,
+        removed_edges.len()
+    )?;
+    writeln!(out)?;
 
-    let dag = graph.dag();
+    let mut dag = graph.dag_without(&removed_edges);
+    drop(graph);
+    dag.index_predecessors();
 
-    compute_edge_types(&dag);
+    compute_edge_types(&dag, &mut out)?;
 
-    let ccs = compute_ccs(&dag);
+    let ccs = compute_ccs(&dag, &mut out)?;
 
     println!("Picking largest connected component...");
     // pick the largest connected component
@@ -171,65 +440,153 @@ fn main() -> std::io::Result<()> {
         .max_by_key(|cc| cc.len())
         .expect("at least one connected components");
 
-    let largest_cc_graph = dag.restricted(largest_cc);
+    let mut largest_cc_graph =
+        oriented_graph(&dag.restricted(largest_cc), &interner, &bidirected_graph);
+    drop(dag);
+    largest_cc_graph.index_predecessors();
 
-    let degrees = compute_graph_degrees(&largest_cc_graph);
-    compute_edge_types(&largest_cc_graph); // to double check this is a DAG
+    let degrees = compute_graph_degrees(&largest_cc_graph, &mut out)?;
+    compute_edge_types(&largest_cc_graph, &mut out)?; // to double check this is a DAG
+
+    if let Some(path) = &opts.output_gfa {
+        println!("Saving largest connected component to {}...", path);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        gfa::writer::write_graph(&mut file, &largest_cc_graph, &sequence_map)?;
+        file.flush()?;
+    }
 
     println!("Searching for a start node...");
-    let start_node = degrees
-        .iter()
-        .find(|(_, degree)| degree.in_degree == 0)
-        .expect("no start node found")
-        .0;
+    let topological_order = largest_cc_graph.topological_order();
+    let start_node = topological_order.first().expect("no start node found");
+
+    writeln!(out, "Start node: {:?}", start_node)?;
+    writeln!(out, "{:?}", degrees.get(start_node).unwrap())?;
+
+    compute_orientation_histogram(&largest_cc_graph, &mut out)?;
+    compute_bubbles(&largest_cc_graph, &mut out)?;
+
+    if let Some(path) = &opts.output_layout {
+        println!("Sorting largest connected component...");
+
+        let layout = largest_cc_graph.path_guided_sort(
+            &stranded_paths,
+            |(id, _)| sequence_map.get(id).map_or(0, |sequence| sequence.len()),
+            LAYOUT_ITERATIONS,
+            LAYOUT_SEED,
+        );
 
-    println!("Start node: {:?}", start_node);
-    println!("{:?}", degrees.get(start_node).unwrap());
+        println!("Saving layout to {}...", path);
 
-    compute_orientation_histogram(&largest_cc_graph);
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "segment\torientation\tposition")?;
+        for ((id, orientation), position) in layout {
+            writeln!(file, "{}\t{}\t{:.2}", id, orientation, position)?;
+        }
+        file.flush()?;
+    }
+
+    println!("Counting paths...");
+    writeln!(
+        out,
+        "Source to sink paths: about 10^{:.2}",
+        largest_cc_graph.log_path_count() / std::f64::consts::LN_10
+    )?;
+    writeln!(out)?;
 
     println!("Visiting the graph, searching {} paths...", opts.path_count);
 
-    let sequences = compute_sequences(
-        &sequence_map,
-        &largest_cc_graph,
-        start_node,
-        opts.path_count,
-    );
+    let sequences = if opts.sample_paths {
+        compute_sampled_sequences(&sequence_map, &largest_cc_graph, opts.path_count, opts.seed)
+    } else {
+        compute_sequences(
+            &sequence_map,
+            &largest_cc_graph,
+            start_node,
+            opts.path_count,
+            opts.max_path_length,
+        )
+    };
 
     for (i, sequence) in sequences.iter().enumerate() {
-        println!("Sequence #{} of length {}", i + 1, sequence.len());
+        writeln!(out, "Sequence #{} of length {}", i + 1, sequence.len())?;
 
         println!("Searching {} (naive)...", opts.pattern);
-        println!(
+        writeln!(
+            out,
             "Occurrences: {:?}\n",
             compute_sequence_occurrences_naive(sequence, &opts.pattern)
-        );
+        )?;
 
         println!("Searching {} (rolling hash)...", opts.pattern);
-        println!(
+        writeln!(
+            out,
             "Occurrences: {:?}\n",
             compute_sequence_occurrences_rolling_hash(sequence, &opts.pattern)
-        );
+        )?;
     }
 
-    compute_kmer_histogram_lb(&sequence_map, &largest_cc_graph, opts.kmer_size);
+    compute_graph_occurrences(&sequence_map, &largest_cc_graph, &opts.pattern, &mut out)?;
+
+    compute_kmer_histogram_lb(&sequence_map, &largest_cc_graph, opts.kmer_size, &mut out)?;
+
+    out.flush()?;
 
     println!("Cleaning up...");
     process::exit(0);
 }
 
+/// Writes the occurrences of the pattern along any path of the graph, each one as the start node,
+/// the offset in its sequence and the nodes it goes through
+fn compute_graph_occurrences(
+    sequence_map: &HashMap<String, String>,
+    graph: &DirectedAcyclicGraph<(String, Orientation), Connection>,
+    pattern: &str,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    println!("Searching {} in the graph (rolling hash)...", pattern);
+
+    let occurrences = graph.find_pattern(
+        pattern.as_bytes(),
+        |node| get_node_sequence(sequence_map, node).into_bytes(),
+        |connection| connection.overlap_length().unwrap_or(0),
+    );
+
+    writeln!(out, "Graph occurrences: {}", occurrences.len())?;
+    for occurrence in occurrences.iter() {
+        let (id, orientation) = &occurrence.start;
+        let path = occurrence
+            .path
+            .iter()
+            .map(|(id, orientation)| format!("{}{}", id, orientation))
+            .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "- {}{} at {}: {}",
+            id,
+            orientation,
+            occurrence.offset,
+            path.join(",")
+        )?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
 fn compute_kmer_histogram_lb(
     sequence_map: &HashMap<String, String>,
-    graph: &DirectedAcyclicGraph<(String, Orientation)>,
+    graph: &DirectedAcyclicGraph<(String, Orientation), Connection>,
     k: usize,
-) {
+    out: &mut dyn Write,
+) -> io::Result<()> {
     println!("Computing k-mer histogram...");
 
     let mut kmer_counts = HashMap::new();
 
-    for node in graph.nodes().iter().progress() {
-        let sequence = get_node_sequence(sequence_map, &node);
+    for node in graph.nodes_iter().progress_count(graph.node_count() as u64) {
+        let sequence = get_node_sequence(sequence_map, node);
         let kmer_counts_node = sequence_kmer_histogram(&sequence, k);
 
         for (kmer, count) in kmer_counts_node {
@@ -241,17 +598,20 @@ fn compute_kmer_histogram_lb(
 
     kmer_counts.sort_by(|a, b| b.1.cmp(&a.1).reverse());
 
-    println!("K-mer histogram (kmers/count):");
+    writeln!(out, "K-mer histogram (kmers/count):")?;
     for (kmer, count) in &kmer_counts {
-        println!("- {}: {}", kmer, count);
+        writeln!(out, "- {}: {}", kmer, count)?;
     }
 
-    println!(
+    writeln!(
+        out,
         "Found {} of {} possible kmers (about {:.2}% coverage)",
         kmer_counts.len(),
         4usize.pow(k as u32),
         (kmer_counts.len() as f64 / 4usize.pow(k as u32) as f64) * 100.0
-    );
+    )?;
+
+    Ok(())
 }
 
 fn sequence_kmer_histogram(sequence: &str, k: usize) -> BTreeMap<String, usize> {
@@ -289,8 +649,33 @@ fn letter_complement(letter: char) -> char {
     }
 }
 
-fn get_node_sequence<'a>(
-    sequence_map: &'a HashMap<String, String>,
+/// Complement of a base, also for the IUPAC ambiguity codes (`N` stays `N`), other characters
+/// are kept as they are
+fn iupac_complement(letter: char) -> char {
+    let complement = match letter.to_ascii_uppercase() {
+        'A' => 'T',
+        'T' | 'U' => 'A',
+        'C' => 'G',
+        'G' => 'C',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        _ => return letter,
+    };
+
+    match letter.is_ascii_lowercase() {
+        true => complement.to_ascii_lowercase(),
+        false => complement,
+    }
+}
+
+fn get_node_sequence(
+    sequence_map: &HashMap<String, String>,
     node: &(String, Orientation),
 ) -> String {
     let (id, orientation) = node;
@@ -312,8 +697,8 @@ fn compute_sequence_occurrences_rolling_hash(sequence: &str, pattern: &str) -> V
 
     let pattern_hash = rl.hash_pattern(&pattern.chars().map(letter_to_number).collect::<Vec<_>>());
 
-    for i in 0..pattern.len() {
-        rl.add_last(chars[i]);
+    for c in chars.iter().take(pattern.len()) {
+        rl.add_last(*c);
     }
 
     for i in pattern.len()..sequence.len() {
@@ -349,60 +734,444 @@ fn compute_sequence_occurrences_naive(sequence: &str, pattern: &str) -> Vec<usiz
     occurrences
 }
 
+/// Concatenates the oriented sequences of the segments of a path, skipping the overlap of the
+/// link between every two consecutive segments, `link` gives the connection between them
+fn get_path_sequence<'a, F>(
+    sequence_map: &HashMap<String, String>,
+    path: &[(String, Orientation)],
+    link: F,
+) -> String
+where
+    F: Fn(&(String, Orientation), &(String, Orientation)) -> Option<&'a Connection>,
+{
+    let mut sequence = String::new();
+
+    for (i, node) in path.iter().enumerate() {
+        let piece = get_node_sequence(sequence_map, node);
+
+        // the overlap with the previous node is already in the sequence
+        let overlap = match i {
+            0 => 0,
+            _ => link(&path[i - 1], node)
+                .and_then(Connection::overlap_length)
+                .unwrap_or(0),
+        };
+
+        sequence.push_str(&piece[overlap.min(piece.len())..]);
+    }
+
+    sequence
+}
+
+/// Maps a graph on the handles of a [CsrGraph] back to oriented segments, the edges get the
+/// connections (links or jumps) they come from
+fn oriented_graph(
+    graph: &DirectedAcyclicGraph<u32>,
+    interner: &Interner,
+    links: &BidirectedGraph<String, Connection>,
+) -> DirectedAcyclicGraph<(String, Orientation), Connection> {
+    let oriented = |node: &u32| {
+        let (segment, orient) = csr::unpack_handle(*node);
+
+        (interner.resolve(segment).to_string(), orient)
+    };
+
+    let mut result = DirectedAcyclicGraph::new();
+
+    for node in graph.nodes_iter() {
+        result.add_node(oriented(node));
+    }
+
+    for (from, to) in graph.edges_iter() {
+        let (from, to) = (oriented(from), oriented(to));
+        let connection = links
+            .link(&from.0, from.1, &to.0, to.1)
+            .cloned()
+            .unwrap_or_default();
+
+        result.add_edge_with(from, to, connection);
+    }
+
+    result
+}
+
+/// Each path followed by its reverse complement walk, i.e. backwards with flipped orientations
+fn stranded_paths(paths: &[Vec<(String, Orientation)>]) -> Vec<Vec<(String, Orientation)>> {
+    paths
+        .iter()
+        .flat_map(|path| {
+            let reversed = path
+                .iter()
+                .rev()
+                .map(|(id, orientation)| (id.clone(), orientation.flip()))
+                .collect();
+
+            [path.clone(), reversed]
+        })
+        .collect()
+}
+
+/// Reconstructs the sequence of every path and walk, saves them to `path` as FASTA and writes
+/// how many there are. `sequence` gives the sequence of a segment, whatever its alphabet, and
+/// `link` the link between two oriented segments, whose overlap is skipped unless the P line
+/// gives its own. Paths through segments without a sequence are skipped and reported.
+fn compute_path_sequences<'a, F, G>(
+    sequence: F,
+    link: G,
+    paths: &[Vec<(String, Orientation)>],
+    path_names: &[String],
+    path_overlaps: &[Vec<String>],
+    path: &str,
+    out: &mut dyn Write,
+) -> io::Result<()>
+where
+    F: Fn(&str) -> Option<&'a String>,
+    G: Fn(&(String, Orientation), &(String, Orientation)) -> Option<&'a Connection>,
+{
+    println!("Reconstructing path sequences...");
+
+    let mut file = BufWriter::new(File::create(path)?);
+
+    let mut lengths = Vec::new();
+    let mut skipped = 0;
+
+    for ((name, segments), overlaps) in path_names.iter().zip(paths).zip(path_overlaps) {
+        if let Some((id, _)) = segments.iter().find(|(id, _)| sequence(id).is_none()) {
+            eprintln!("Skipped path {}, segment {} has no sequence", name, id);
+            skipped += 1;
+            continue;
+        }
+
+        let mut merged = String::new();
+
+        for (i, (id, orientation)) in segments.iter().enumerate() {
+            let piece = sequence(id).map_or("", String::as_str);
+            let piece = match orientation {
+                Orientation::Forward => piece.to_string(),
+                Orientation::Reverse => piece.chars().rev().map(iupac_complement).collect(),
+            };
+
+            // the overlap with the previous segment is already in the sequence
+            let overlap = match i {
+                0 => 0,
+                _ => match overlaps.get(i - 1).filter(|cigar| *cigar != "*") {
+                    Some(cigar) => gfa::overlap_length(cigar).unwrap_or(0),
+                    None => link(&segments[i - 1], &segments[i])
+                        .and_then(Connection::overlap_length)
+                        .unwrap_or(0),
+                },
+            };
+
+            merged.push_str(&piece[overlap.min(piece.len())..]);
+        }
+
+        fasta::write_record(&mut file, name, &merged)?;
+
+        lengths.push(merged.len());
+    }
+
+    file.flush()?;
+
+    writeln!(
+        out,
+        "Path sequences: {} ({} skipped)",
+        lengths.len(),
+        skipped
+    )?;
+    writeln!(out, "- Total length: {}", lengths.iter().sum::<usize>())?;
+    writeln!(
+        out,
+        "- Max length: {}",
+        lengths.iter().max().copied().unwrap_or(0)
+    )?;
+    writeln!(out)?;
+
+    Ok(())
+}
+
+/// Like [compute_sequences] but the paths are sampled uniformly among all the source to sink
+/// paths of the graph
+fn compute_sampled_sequences(
+    sequence_map: &HashMap<String, String>,
+    graph: &DirectedAcyclicGraph<(String, Orientation), Connection>,
+    count: usize,
+    seed: u64,
+) -> Vec<String> {
+    graph
+        .sample_paths(count, seed)
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            println!("Path #{} of length {}", i + 1, path.len());
+
+            get_path_sequence(sequence_map, path, |from, to| graph.edge(from, to))
+        })
+        .collect()
+}
+
 fn compute_sequences(
     sequence_map: &HashMap<String, String>,
-    graph: &DirectedAcyclicGraph<(String, Orientation)>,
+    graph: &DirectedAcyclicGraph<(String, Orientation), Connection>,
     start_node: &(String, Orientation),
     count: usize,
+    max_length: Option<usize>,
 ) -> Vec<String> {
     let mut sequences = vec![];
 
-    let mut path_counter = 0;
-    graph.all_paths(start_node, |path| {
-        println!("Path #{} of length {}", path_counter + 1, path.len());
+    let limits = PathLimits {
+        max_count: Some(count),
+        max_length,
+    };
 
-        let mut sequence = String::new();
-        for node in path {
-            let piece = get_node_sequence(sequence_map, &node);
-            sequence.push_str(&piece);
-        }
+    graph.visit_paths(start_node, None, limits, |path| {
+        println!("Path #{} of length {}", sequences.len() + 1, path.len());
 
-        sequences.push(sequence);
+        sequences.push(get_path_sequence(sequence_map, &path, |from, to| {
+            graph.edge(from, to)
+        }));
 
-        path_counter += 1;
-        path_counter < count
+        true
     });
 
     sequences
 }
 
-fn compute_orientation_histogram(graph: &impl Graph<(String, Orientation)>) {
+/// Writes how many unitigs (non-branching walks) the graph has and how long they are, the walks
+/// are merged in single segments only to save them to `path` as GFA
+fn compute_unitigs(
+    graph: &BidirectedGraph<String, Connection>,
+    sequence_map: &HashMap<String, String>,
+    path: Option<&str>,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    println!("Computing unitigs...");
+    // segments across a jump are not merged
+    let walks = graph.unitig_walks(|connection| connection.overlap_length().is_some());
+
+    // the links inside a walk are the only links of the sides they join
+    let links = graph.edge_count() - walks.iter().map(|walk| walk.len() - 1).sum::<usize>();
+
+    writeln!(out, "Unitigs: {} ({} links)", walks.len(), links)?;
+    writeln!(
+        out,
+        "- Max segments: {}",
+        walks.iter().map(|walk| walk.len()).max().unwrap_or(0)
+    )?;
+    writeln!(
+        out,
+        "- Max length: {}",
+        walks
+            .iter()
+            .map(|walk| graph.walk_length(
+                walk,
+                |id| sequence_map.get(id).map_or(0, |sequence| sequence.len()),
+                Connection::overlap_length,
+            ))
+            .max()
+            .unwrap_or(0)
+    )?;
+    writeln!(out)?;
+
+    // the merged sequences are only built to be saved
+    if let Some(path) = path {
+        println!("Compacting unitigs...");
+        let (unitigs, unitig_graph) = graph.compact_unitigs(
+            // links can point to segments without a sequence
+            |node| match sequence_map.contains_key(&node.0) {
+                true => get_node_sequence(sequence_map, node),
+                false => String::new(),
+            },
+            Connection::overlap_length,
+        );
+
+        println!("Saving unitigs to {}...", path);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        gfa::writer::write_unitigs(&mut file, &unitigs, &unitig_graph)?;
+        file.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Writes a histogram of the sizes of the strongly connected components, the components with more
+/// than one node contain the cycles removed to get the DAG
+fn compute_sccs<V, E>(graph: &AdjacencyGraph<V, E>, out: &mut dyn Write) -> io::Result<()>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    println!("Computing strongly connected components...");
+    let (condensation, sccs) = graph.condensation();
+
+    let histogram = sccs
+        .iter()
+        .map(|scc| scc.len())
+        .fold(BTreeMap::new(), |mut acc, len| {
+            *acc.entry(len).or_insert(0) += 1;
+            acc
+        });
+
+    writeln!(
+        out,
+        "Strongly connected components: {} ({} with cycles)",
+        sccs.len(),
+        sccs.iter().filter(|scc| scc.len() > 1).count()
+    )?;
+    writeln!(
+        out,
+        "Condensation: {} nodes, {} edges",
+        condensation.node_count(),
+        condensation.edge_count()
+    )?;
+
+    writeln!(out, "Strongly connected components histogram (size/count):")?;
+    for (size, count) in histogram.iter() {
+        writeln!(out, "- {}: {}", size, count)?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+/// Writes how many edges each feedback arc set strategy removes, and how many of them are
+/// traversed by some path
+fn compute_feedback_arc_sets<V, E>(
+    graph: &AdjacencyGraph<V, E>,
+    paths: &[Vec<V>],
+    out: &mut dyn Write,
+) -> io::Result<()>
+where
+    V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
+{
+    let path_edges = paths
+        .iter()
+        .flat_map(|path| path.windows(2))
+        .map(|step| (step[0].clone(), step[1].clone()))
+        .collect::<BTreeSet<_>>();
+
+    writeln!(out, "Feedback arc sets (strategy/removed edges):")?;
+    for strategy in FeedbackArcSetStrategy::ALL {
+        println!("Computing feedback arc set ({})...", strategy);
+        let removed = graph.feedback_arc_set(strategy, paths);
+
+        writeln!(
+            out,
+            "- {}: {} ({} traversed by paths)",
+            strategy,
+            removed.len(),
+            removed.intersection(&path_edges).count()
+        )?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+/// Writes how many superbubbles the DAG has and histograms of their sizes and nesting depths
+fn compute_bubbles<E>(
+    graph: &DirectedAcyclicGraph<(String, Orientation), E>,
+    out: &mut dyn Write,
+) -> io::Result<()>
+where
+    E: Clone + Default,
+{
+    println!("Computing superbubbles...");
+    let bubbles = graph.superbubbles();
+
+    // if the graph has both strands of a region, each bubble is also found on the opposite
+    // strand from the flipped exit to the flipped entrance, only the smallest of the two is kept
+    let endpoints = bubbles
+        .iter()
+        .map(|bubble| (&bubble.entrance, &bubble.exit))
+        .collect::<BTreeSet<_>>();
+    let bubbles = bubbles
+        .iter()
+        .filter(|bubble| {
+            let twin = (
+                (bubble.exit.0.clone(), bubble.exit.1.flip()),
+                (bubble.entrance.0.clone(), bubble.entrance.1.flip()),
+            );
+
+            !endpoints.contains(&(&twin.0, &twin.1))
+                || (&bubble.entrance, &bubble.exit) <= (&twin.0, &twin.1)
+        })
+        .collect::<Vec<_>>();
+
+    let size_histogram =
+        bubbles
+            .iter()
+            .map(|bubble| bubble.size())
+            .fold(BTreeMap::new(), |mut acc, size| {
+                *acc.entry(size).or_insert(0) += 1;
+                acc
+            });
+
+    let depth_histogram =
+        bubbles
+            .iter()
+            .map(|bubble| bubble.depth)
+            .fold(BTreeMap::new(), |mut acc, depth| {
+                *acc.entry(depth).or_insert(0) += 1;
+                acc
+            });
+
+    writeln!(
+        out,
+        "Superbubbles: {} ({} top level)",
+        bubbles.len(),
+        bubbles
+            .iter()
+            .filter(|bubble| bubble.parent.is_none())
+            .count()
+    )?;
+
+    writeln!(out, "Superbubble size histogram (nodes/count):")?;
+    for (size, count) in size_histogram.iter() {
+        writeln!(out, "- {}: {}", size, count)?;
+    }
+
+    writeln!(out, "Superbubble depth histogram (depth/count):")?;
+    for (depth, count) in depth_histogram.iter() {
+        writeln!(out, "- {}: {}", depth, count)?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+fn compute_orientation_histogram<E>(
+    graph: &impl Graph<(String, Orientation), E>,
+    out: &mut dyn Write,
+) -> io::Result<()>
+where
+    E: Clone + Default,
+{
     let orientation_histogram =
         graph
-            .nodes()
-            .iter()
+            .nodes_iter()
             .map(|node| node.1)
             .fold(BTreeMap::new(), |mut acc, orientation| {
                 *acc.entry(orientation).or_insert(0) += 1;
                 acc
             });
 
-    println!("Orientation histogram:");
+    writeln!(out, "Orientation histogram:")?;
     for (orientation, count) in orientation_histogram.iter() {
-        println!("- {:?}: {}", orientation, count);
+        writeln!(out, "- {:?}: {}", orientation, count)?;
     }
-    println!();
+    writeln!(out)?;
+
+    Ok(())
 }
 
-fn compute_ccs<V>(graph: &impl Graph<V>) -> Vec<Vec<V>>
+fn compute_ccs<V, E>(graph: &impl Graph<V, E>, out: &mut dyn Write) -> io::Result<Vec<Vec<V>>>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
-    println!("Computing connected components...");
-    let ccs = graph
-        .to_adjecency_graph()
-        .undirected()
-        .connected_components();
+    let ccs = graph.to_adjecency_graph().compute_ccs();
 
     println!("Computing sizes histogram...");
     let hist: BTreeMap<_, _> = ccs
@@ -413,48 +1182,55 @@ where
             acc
         });
 
-    println!("Connected components histogram (size/count):");
+    writeln!(out, "Connected components histogram (size/count):")?;
     for (size, count) in hist.iter() {
-        println!("- {}: {}", size, count);
+        writeln!(out, "- {}: {}", size, count)?;
     }
-    println!();
+    writeln!(out)?;
 
-    ccs
+    Ok(ccs)
 }
 
-fn compute_edge_types<V>(graph: &impl Graph<V>) -> BTreeMap<(V, V), graph::edge_types::EdgeType>
+fn compute_edge_types<V, E>(
+    graph: &impl Graph<V, E>,
+    out: &mut dyn Write,
+) -> io::Result<BTreeMap<(V, V), graph::edge_types::EdgeType>>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
     println!("Computing edge types...");
     let edge_types = graph.to_adjecency_graph().compute_edge_types();
 
     println!("Computing edge types histogram...");
-    let histogram = edge_types.iter().map(|(_, edge_type)| edge_type).fold(
-        BTreeMap::new(),
-        |mut acc, edge_type| {
-            *acc.entry(edge_type.clone()).or_insert(0) += 1;
+    let histogram = edge_types
+        .values()
+        .fold(BTreeMap::new(), |mut acc, edge_type| {
+            *acc.entry(*edge_type).or_insert(0) += 1;
             acc
-        },
-    );
+        });
 
-    println!("Node count: {}", graph.nodes().len());
-    println!(
+    writeln!(out, "Node count: {}", graph.node_count())?;
+    writeln!(
+        out,
         "Edge count: {}, Total edge count: {}",
-        graph.edges().len(),
+        graph.edge_count(),
         edge_types.len()
-    );
+    )?;
 
-    println!("Edge types histogram (type/count):");
+    writeln!(out, "Edge types histogram (type/count):")?;
     for (edge_type, count) in histogram.iter() {
-        println!("- {:?}: {}", edge_type, count);
+        writeln!(out, "- {:?}: {}", edge_type, count)?;
     }
-    println!();
+    writeln!(out)?;
 
-    edge_types
+    Ok(edge_types)
 }
 
-fn compute_shuffled_graph<V>(graph: &AdjacencyGraph<V>) -> AdjacencyGraph<V>
+fn compute_shuffled_graph<V>(
+    graph: &AdjacencyGraph<V>,
+    out: &mut dyn Write,
+) -> io::Result<AdjacencyGraph<V>>
 where
     V: Ord + Eq + Clone + Debug,
 {
@@ -462,7 +1238,7 @@ where
 
     let mut g2 = AdjacencyGraph::new();
 
-    let mut shuffled_nodes: Vec<_> = graph.nodes().into_iter().collect::<Vec<_>>();
+    let mut shuffled_nodes: Vec<_> = graph.nodes_iter().cloned().collect::<Vec<_>>();
     shuffled_nodes.shuffle(&mut rand::thread_rng());
 
     for node in shuffled_nodes.iter() {
@@ -470,20 +1246,20 @@ where
     }
 
     let mut shuffled_map = BTreeMap::new();
-    for (i, node) in graph.nodes().iter().enumerate() {
-        shuffled_map.insert(node.clone(), shuffled_nodes[i].clone());
+    for (i, node) in graph.nodes_iter().enumerate() {
+        shuffled_map.insert(node, shuffled_nodes[i].clone());
     }
 
-    for edge in graph.edges() {
+    for (from, to) in graph.edges_iter() {
         g2.add_edge(
-            shuffled_map.get(&edge.0).unwrap().clone(),
-            shuffled_map.get(&edge.1).unwrap().clone(),
+            shuffled_map.get(from).unwrap().clone(),
+            shuffled_map.get(to).unwrap().clone(),
         );
     }
 
-    compute_edge_types(&g2);
+    compute_edge_types(&g2, out)?;
 
-    g2
+    Ok(g2)
 }
 
 #[derive(Debug)]
@@ -495,9 +1271,13 @@ struct NodeDegree {
 
 /// This function prints the number of nodes, edges and a histogram of the degrees of the nodes
 /// in the graph (computing the degrees might take a long time)
-fn compute_graph_degrees<V>(graph: &impl Graph<V>) -> BTreeMap<V, NodeDegree>
+fn compute_graph_degrees<V, E>(
+    graph: &impl Graph<V, E>,
+    out: &mut dyn Write,
+) -> io::Result<BTreeMap<V, NodeDegree>>
 where
     V: Ord + Eq + Clone + Debug,
+    E: Clone + Default,
 {
     println!("Computing graph stats...");
 
@@ -507,72 +1287,68 @@ where
 
     println!("Computing nodes degrees...");
 
-    let progress_bar = indicatif::ProgressBar::new(graph.edges().len() as u64);
+    let in_degrees = graph.in_degrees();
+    let progress_bar = indicatif::ProgressBar::new(graph.node_count() as u64);
 
-    for node in graph.nodes() {
-        vertices_degrees.insert(node.clone(), 0);
-        vertices_in_degrees.insert(node.clone(), 0);
-        vertices_out_degrees.insert(node.clone(), 0);
-    }
-
-    for (from, tos) in graph.adjacencies() {
-        *vertices_degrees.entry(from.clone()).or_insert(0) += tos.len();
-        *vertices_out_degrees.entry(from.clone()).or_insert(0) += tos.len();
+    for node in graph.nodes_iter() {
+        progress_bar.inc(1);
 
-        for to in tos {
-            progress_bar.inc(1);
+        let in_degree = in_degrees.get(node).copied().unwrap_or(0);
+        let out_degree = graph.out_degree(node);
 
-            *vertices_degrees.entry(to.clone()).or_insert(0) += 1;
-            *vertices_in_degrees.entry(to.clone()).or_insert(0) += 1;
-        }
+        vertices_degrees.insert(node, in_degree + out_degree);
+        vertices_in_degrees.insert(node, in_degree);
+        vertices_out_degrees.insert(node, out_degree);
     }
 
     progress_bar.finish();
 
     println!("Computing histogram...");
-    let histogram: BTreeMap<usize, usize> = vertices_degrees
-        .iter()
-        .map(|(_, degree)| *degree)
-        .fold(BTreeMap::new(), |mut acc, degree| {
-            *acc.entry(degree).or_insert(0) += 1;
-            acc
-        });
-    let histogram_in: BTreeMap<usize, usize> = vertices_in_degrees
-        .iter()
-        .map(|(_, degree)| *degree)
-        .fold(BTreeMap::new(), |mut acc, degree| {
-            *acc.entry(degree).or_insert(0) += 1;
-            acc
-        });
-    let histogram_out: BTreeMap<usize, usize> = vertices_out_degrees
-        .iter()
-        .map(|(_, degree)| *degree)
-        .fold(BTreeMap::new(), |mut acc, degree| {
-            *acc.entry(degree).or_insert(0) += 1;
-            acc
-        });
+    let histogram: BTreeMap<usize, usize> =
+        vertices_degrees
+            .values()
+            .copied()
+            .fold(BTreeMap::new(), |mut acc, degree| {
+                *acc.entry(degree).or_insert(0) += 1;
+                acc
+            });
+    let histogram_in: BTreeMap<usize, usize> =
+        vertices_in_degrees
+            .values()
+            .copied()
+            .fold(BTreeMap::new(), |mut acc, degree| {
+                *acc.entry(degree).or_insert(0) += 1;
+                acc
+            });
+    let histogram_out: BTreeMap<usize, usize> =
+        vertices_out_degrees
+            .values()
+            .copied()
+            .fold(BTreeMap::new(), |mut acc, degree| {
+                *acc.entry(degree).or_insert(0) += 1;
+                acc
+            });
 
-    println!("Stats:");
-    println!("- Nodes: {}", graph.nodes().len());
-    println!("- Edges: {}", graph.edges().len());
+    writeln!(out, "Stats:")?;
+    writeln!(out, "- Nodes: {}", graph.node_count())?;
+    writeln!(out, "- Edges: {}", graph.edge_count())?;
 
-    println!("Graph degrees histogram (degree/count):");
+    writeln!(out, "Graph degrees histogram (degree/count):")?;
     for (degree, count) in histogram.iter() {
-        println!("- {}: {}", degree, count);
+        writeln!(out, "- {}: {}", degree, count)?;
     }
-    println!("In-degrees histogram (degree/count):");
+    writeln!(out, "In-degrees histogram (degree/count):")?;
     for (degree, count) in histogram_in.iter() {
-        println!("- {}: {}", degree, count);
+        writeln!(out, "- {}: {}", degree, count)?;
     }
-    println!("Out-degrees histogram (degree/count):");
+    writeln!(out, "Out-degrees histogram (degree/count):")?;
     for (degree, count) in histogram_out.iter() {
-        println!("- {}: {}", degree, count);
+        writeln!(out, "- {}: {}", degree, count)?;
     }
-    println!();
+    writeln!(out)?;
 
-    graph
-        .nodes()
-        .iter()
+    Ok(graph
+        .nodes_iter()
         .map(|node| {
             (
                 node.clone(),
@@ -587,7 +1363,7 @@ where
                 },
             )
         })
-        .collect()
+        .collect())
 }
 
 // fn compute_compact_graph<V>(graph: &mut UndirectedGraph<V>) -> UndirectedGraph<V>
@@ -1,5 +1,6 @@
 use std::{collections::VecDeque, fmt::Debug};
 
+#[derive(Debug, Clone)]
 pub struct RollingHasher<T: Into<u64> + Clone> {
     modulus: u64,
     alphabet_size: u64,
@@ -37,6 +38,11 @@ where
         }
     }
 
+    /// Values currently in the window
+    pub fn word(&self) -> &VecDeque<T> {
+        &self.current_word
+    }
+
     pub fn compare(&self, lhs: &Hashed, rhs: &Hashed) -> bool {
         let (lhs, rhs) = if lhs.offset < rhs.offset {
             (lhs, rhs)